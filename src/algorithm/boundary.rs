@@ -0,0 +1,136 @@
+use crate::{
+    data::{
+        flow::FACES,
+        properties::{Boundaries, Boundary},
+    },
+    math::{Coords, CoordsDiff},
+};
+
+/// Domain face as seen by a single scalar field.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Edge {
+    /// Nothing crosses the face.
+    Closed,
+    /// Face continues on the opposite side of the domain.
+    Wrap,
+    /// Face opens to an exterior holding the value.
+    Fixed(f32),
+}
+
+/// Resolved boundary of the domain for a single scalar field.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Bounds {
+    size: Coords,
+    // indexed in `FACES` order
+    edges: [Edge; 6],
+}
+
+impl Bounds {
+    fn new<F>(boundaries: &Boundaries, size: Coords, value: F) -> Self
+    where
+        F: Fn(&Boundary) -> f32,
+    {
+        let edges = FACES.map(|(face, _)| match boundaries.face(face) {
+            Boundary::Wall => Edge::Closed,
+            Boundary::Periodic => match boundaries.face(face.opposite()) {
+                Boundary::Periodic => Edge::Wrap,
+                _ => Edge::Closed,
+            },
+            b => Edge::Fixed(value(b)),
+        });
+        Self { size, edges }
+    }
+
+    pub fn pressure(boundaries: &Boundaries, size: Coords, channel: usize) -> Self {
+        Self::new(boundaries, size, |b| match b {
+            Boundary::Inflow { pressure, .. } => pressure.get(channel).copied().unwrap_or(0.0),
+            _ => 0.0,
+        })
+    }
+
+    pub fn velocity(boundaries: &Boundaries, size: Coords, axis: usize) -> Self {
+        Self::new(boundaries, size, |b| match b {
+            Boundary::Inflow { velocity, .. } => velocity[axis],
+            _ => 0.0,
+        })
+    }
+
+    /// Same shape of the boundary with every exterior value zero.
+    pub fn homogeneous(&self) -> Self {
+        let edges = self.edges.map(|e| match e {
+            Edge::Fixed(_) => Edge::Fixed(0.0),
+            e => e,
+        });
+        Self { edges, ..*self }
+    }
+
+    /// Maps position into the domain wrapping periodic axes, or returns the
+    /// edge the position lies behind.
+    pub fn resolve(&self, p: CoordsDiff) -> Result<Coords, Edge> {
+        let size = [self.size.0, self.size.1, self.size.2];
+        let mut res = [p.0, p.1, p.2];
+        for (axis, v) in res.iter_mut().enumerate() {
+            let len = size[axis] as isize;
+            if (0..len).contains(v) {
+                continue;
+            }
+            let face = if *v < 0 { axis + 3 } else { axis };
+            match self.edges[face] {
+                Edge::Wrap => *v = v.rem_euclid(len),
+                edge => return Err(edge),
+            }
+        }
+        Ok(Coords(res[0] as usize, res[1] as usize, res[2] as usize))
+    }
+
+    /// Neighbour of the cell behind `FACES[face]`.
+    pub fn neighbour(&self, c: &Coords, face: usize) -> Result<Coords, Edge> {
+        self.resolve(CoordsDiff::from(*c) + FACES[face].1)
+    }
+
+    /// Keeps position inside the domain along axes closed on that side.
+    pub fn confine(&self, pos: &mut [f32; 3]) {
+        let size = [self.size.0, self.size.1, self.size.2];
+        for (axis, v) in pos.iter_mut().enumerate() {
+            if self.edges[axis + 3] == Edge::Closed {
+                *v = v.max(0.0);
+            }
+            if self.edges[axis] == Edge::Closed {
+                *v = v.min((size[axis] - 1) as f32);
+            }
+        }
+    }
+
+    /// Whether position lies within one cell of the domain, NaN never does.
+    pub fn contains(&self, pos: [f32; 3]) -> bool {
+        let size = [self.size.0, self.size.1, self.size.2];
+        (0..3).all(|axis| (-1.0..=size[axis] as f32).contains(&pos[axis]))
+    }
+
+    /// Shortens the path from `from` to `to` so it ends within one cell of the
+    /// domain and moves at most the domain length along periodic axes. The
+    /// end is not wrapped, so the path still crosses the seam.
+    pub fn limit(&self, from: [f32; 3], to: &mut [f32; 3]) {
+        let size = [self.size.0, self.size.1, self.size.2];
+        for (axis, v) in to.iter_mut().enumerate() {
+            let len = size[axis] as f32;
+            *v = match self.edges[axis] {
+                Edge::Wrap => from[axis] + (*v - from[axis]).clamp(-len, len),
+                _ => v.clamp(-1.0, len),
+            };
+        }
+    }
+
+    /// Brings position within one cell of the domain, the exterior holds the
+    /// same value at any distance. Periodic axes are wrapped instead.
+    pub fn enclose(&self, pos: &mut [f32; 3]) {
+        let size = [self.size.0, self.size.1, self.size.2];
+        for (axis, v) in pos.iter_mut().enumerate() {
+            let len = size[axis] as f32;
+            *v = match self.edges[axis] {
+                Edge::Wrap => v.rem_euclid(len),
+                _ => v.clamp(-1.0, len),
+            };
+        }
+    }
+}
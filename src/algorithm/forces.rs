@@ -1,73 +1,249 @@
 use crate::{
-    math::{coords, iterator, Slice3D, Slice3DMut},
-    Sized3D,
+    data::{
+        flow::{FlowFlags, FACES},
+        properties::Buoyancy,
+    },
+    math::{coords, parallel, Coords, Flat3D, Slice3D},
 };
 
-pub fn decay_velocity<DST, SRC>(dst: &mut DST, src: &SRC, coefficient: f32)
+use super::boundary::{Bounds, Edge};
+
+pub fn decay<DST, SRC>(dst: &mut DST, src: &SRC, coefficient: f32)
 where
-    DST: for<'a> Slice3DMut<Output<'a> = &'a mut f32> + Sized3D,
-    SRC: for<'a> Slice3D<Output<'a> = &'a f32>,
+    DST: Flat3D<Item = f32>,
+    SRC: for<'a> Slice3D<Output<'a> = &'a f32> + Sync + 'static,
 {
-    for c in iterator::iterate(dst.size()) {
-        *dst.slice_mut(&c) = src.slice(&c) * coefficient;
-    }
+    parallel::for_each_mut(dst, |c, v| *v = src.slice(c) * coefficient);
 }
 
-pub fn pressuarize<VEL, PR, const PR_SIZE: usize>(vel: &mut VEL, pr: &PR, force: f32)
-where
-    VEL: for<'a> Slice3DMut<Output<'a> = [&'a mut f32; 3]> + 'static,
-    PR: for<'a> Slice3D<Output<'a> = [&'a f32; PR_SIZE]> + Sized3D + 'static,
+pub fn pressuarize<DST, PR, BLK, const PR_SIZE: usize>(
+    vel: [&mut DST; 3],
+    pr: &PR,
+    blockage: &BLK,
+    bounds: &[Bounds; PR_SIZE],
+    weights: &[f32; PR_SIZE],
+    force: f32,
+) where
+    DST: Flat3D<Item = f32>,
+    PR: for<'a> Slice3D<Output<'a> = [&'a f32; PR_SIZE]> + Sync + 'static,
+    BLK: for<'a> Slice3D<Output<'a> = &'a FlowFlags> + Sync + 'static,
 {
-    let size = pr.size();
-    let sum = |c| -> f32 { pr.slice(&c).into_iter().fold(0.0f32, |a, i| a + *i) };
-    for c in iterator::iterate(size - coords::ONES) {
+    let sum = |c: &Coords| -> f32 {
+        pr.slice(c)
+            .into_iter()
+            .zip(weights)
+            .fold(0.0f32, |a, (p, w)| a + p * w)
+    };
+    let exterior = |c: &Coords, face| -> f32 {
+        bounds
+            .iter()
+            .zip(weights)
+            .fold(0.0f32, |a, (b, w)| match b.neighbour(c, face) {
+                Err(Edge::Fixed(v)) => a + v * w,
+                _ => a,
+            })
+    };
+    // Pressure difference across the face is added to the velocity of both
+    // cells sharing it
+    let push = |c: &Coords, src_press: f32, face: usize| -> f32 {
+        if blockage.slice(c).contains(FACES[face].0) {
+            return 0.0;
+        }
+        let other = match bounds[0].neighbour(c, face) {
+            Ok(n) => sum(&n),
+            Err(Edge::Fixed(_)) => exterior(c, face),
+            Err(_) => return 0.0,
+        };
+        other - src_press
+    };
+    parallel::for_each_mut3(vel, |c, v| {
         let src_press = sum(c);
-        let force_x = sum(c + coords::X_FORW) - src_press;
-        let force_y = sum(c + coords::Y_FORW) - src_press;
-        let force_z = sum(c + coords::Z_FORW) - src_press;
-
-        let vel0 = vel.slice_mut(&c);
-        *vel0[0] += force * force_x;
-        *vel0[1] += force * force_y;
-        *vel0[2] += force * force_z;
+        for (axis, v) in v.into_iter().enumerate() {
+            *v += force * (push(c, src_press, axis + 3) + push(c, src_press, axis));
+        }
+    });
+}
 
-        *vel.slice_mut(&(c + coords::X_FORW))[0] -= force * force_x;
-        *vel.slice_mut(&(c + coords::Y_FORW))[1] -= force * force_y;
-        *vel.slice_mut(&(c + coords::Z_FORW))[2] -= force * force_z;
+/// Accelerates velocity against gravity in proportion to how much hotter
+/// than ambient a cell is, and along it in proportion to its density. Solid
+/// cells are left alone.
+pub fn buoyancy<DST, SRC, BLK>(
+    vel: [&mut DST; 3],
+    temperature: &SRC,
+    density: &SRC,
+    blockage: &BLK,
+    props: &Buoyancy,
+    dt: f32,
+) where
+    DST: Flat3D<Item = f32>,
+    SRC: for<'a> Slice3D<Output<'a> = &'a f32> + Sync + 'static,
+    BLK: for<'a> Slice3D<Output<'a> = &'a FlowFlags> + Sync + 'static,
+{
+    for (dst, g) in vel.into_iter().zip(props.gravity) {
+        if g == 0.0 {
+            continue;
+        }
+        parallel::for_each_mut(dst, |c, v| {
+            if blockage.slice(c).is_all() {
+                return;
+            }
+            let heat = temperature.slice(c) - props.ambient_temperature;
+            let lift = props.alpha * density.slice(c) - props.beta * heat;
+            *v += dt * lift * g;
+        });
     }
 }
 
-pub fn generate_vortexes<VORT, VEL>(vorticies: &mut VORT, vel: &VEL)
+pub fn generate_vortexes<VORT, VEL>(vorticies: &mut VORT, vel: &VEL, bounds: &Bounds)
 where
-    VORT: for<'a> Slice3DMut<Output<'a> = &'a mut f32> + Sized3D,
-    VEL: for<'a> Slice3D<Output<'a> = [&'a f32; 3]>,
+    VORT: Flat3D<Item = f32>,
+    VEL: for<'a> Slice3D<Output<'a> = [&'a f32; 3]> + Sync + 'static,
 {
-    let size = vorticies.size();
-    for c in iterator::iterate_range(coords::ONES.into(), size - coords::ONES) {
-        let x = vel.slice(&(c + coords::Y_FORW))[0] - vel.slice(&(c + coords::Y_BACK))[0];
-        let y = vel.slice(&(c + coords::X_FORW))[1] - vel.slice(&(c + coords::X_BACK))[1];
-        let z = vel.slice(&(c + coords::Z_FORW))[2] - vel.slice(&(c + coords::Z_BACK))[2];
-        *vorticies.slice_mut(&c) = ((x - y - z) * 0.5).abs()
-    }
+    parallel::for_each_mut(vorticies, |c, v| {
+        let vortex = || -> Result<f32, Edge> {
+            let at = |d| bounds.resolve(coords::CoordsDiff::from(*c) + d);
+            let x = vel.slice(&at(coords::Y_FORW)?)[0] - vel.slice(&at(coords::Y_BACK)?)[0];
+            let y = vel.slice(&at(coords::X_FORW)?)[1] - vel.slice(&at(coords::X_BACK)?)[1];
+            let z = vel.slice(&at(coords::Z_FORW)?)[2] - vel.slice(&at(coords::Z_BACK)?)[2];
+            Ok(((x - y - z) * 0.5).abs())
+        };
+        *v = vortex().unwrap_or(0.0);
+    });
 }
 
-pub fn apply_vortex<VEL, VORT>(vel: &mut VEL, vorticies: &VORT, force: f32)
+pub fn apply_vortex<DST, VORT>(vel: [&mut DST; 3], vorticies: &VORT, bounds: &Bounds, force: f32)
 where
-    VEL: for<'a> Slice3DMut<Output<'a> = [&'a mut f32; 3]> + 'static,
-    VORT: for<'a> Slice3D<Output<'a> = &'a f32> + Sized3D + 'static,
+    DST: Flat3D<Item = f32>,
+    VORT: for<'a> Slice3D<Output<'a> = &'a f32> + Sync + 'static,
 {
-    let size = vorticies.size();
-    for c in iterator::iterate_range(coords::ONES.into(), size - coords::ONES) {
-        let lr = vorticies.slice(&(c + coords::X_FORW)) - vorticies.slice(&(c + coords::X_BACK));
-        let ud = vorticies.slice(&(c + coords::Y_FORW)) - vorticies.slice(&(c + coords::Y_BACK));
-        let bf = vorticies.slice(&(c + coords::Z_FORW)) - vorticies.slice(&(c + coords::Z_BACK));
+    let vortex = |c: &Coords| -> Option<[f32; 3]> {
+        let gradient = || -> Result<(f32, f32, f32), Edge> {
+            let at = |d| bounds.resolve(coords::CoordsDiff::from(*c) + d);
+            let lr = vorticies.slice(&at(coords::X_FORW)?) - vorticies.slice(&at(coords::X_BACK)?);
+            let ud = vorticies.slice(&at(coords::Y_FORW)?) - vorticies.slice(&at(coords::Y_BACK)?);
+            let bf = vorticies.slice(&at(coords::Z_FORW)?) - vorticies.slice(&at(coords::Z_BACK)?);
+            Ok((lr, ud, bf))
+        };
+        let (lr, ud, bf) = gradient().ok()?;
         let length = (lr * lr + ud * ud + bf * bf).sqrt();
-        if length > f32::EPSILON {
-            let magnitude = vorticies.slice(&c) * force / length;
+        if length <= f32::EPSILON {
+            return None;
+        }
+        let magnitude = vorticies.slice(c) * force / length;
+        Some([-ud * magnitude, lr * magnitude, bf * magnitude])
+    };
+    parallel::for_each_mut3(vel, |c, v| {
+        if let Some(push) = vortex(c) {
+            for (v, push) in v.into_iter().zip(push) {
+                *v += push;
+            }
+        }
+    });
+}
 
-            *vel.slice_mut(&c)[0] -= ud * magnitude;
-            *vel.slice_mut(&c)[1] += lr * magnitude;
-            *vel.slice_mut(&c)[2] += bf * magnitude;
+/// Difference of `value` across the cell along `axis`, in cell units.
+/// Neighbours behind blocked faces or without a value are left out, making
+/// it one-sided, or zero when neither is usable.
+fn difference<BLK, F>(c: &Coords, axis: usize, blockage: &BLK, bounds: &Bounds, value: F) -> f32
+where
+    BLK: for<'a> Slice3D<Output<'a> = &'a FlowFlags> + 'static,
+    F: Fn(Result<Coords, Edge>) -> Option<f32>,
+{
+    let side = |face: usize| {
+        if blockage.slice(c).contains(FACES[face].0) {
+            return None;
         }
+        value(bounds.neighbour(c, face))
+    };
+    let here = || value(Ok(*c)).unwrap_or(0.0);
+    match (side(axis), side(axis + 3)) {
+        (Some(forw), Some(back)) => (forw - back) * 0.5,
+        (Some(forw), None) => forw - here(),
+        (None, Some(back)) => here() - back,
+        (None, None) => 0.0,
     }
 }
+
+/// Full curl of the velocity in cell units, zero in solid cells.
+pub fn generate_curl<CURL, VEL, BLK>(
+    curl: &mut CURL,
+    vel: &VEL,
+    blockage: &BLK,
+    bounds: &[Bounds; 3],
+) where
+    CURL: Flat3D<Item = [f32; 3]>,
+    VEL: for<'a> Slice3D<Output<'a> = [&'a f32; 3]> + Sync + 'static,
+    BLK: for<'a> Slice3D<Output<'a> = &'a FlowFlags> + Sync + 'static,
+{
+    parallel::for_each_mut(curl, |c, w| *w = curl_at(c, vel, blockage, bounds));
+}
+
+/// Curl of a single cell, see [`generate_curl`].
+pub(crate) fn curl_at<VEL, BLK>(
+    c: &Coords,
+    vel: &VEL,
+    blockage: &BLK,
+    bounds: &[Bounds; 3],
+) -> [f32; 3]
+where
+    VEL: for<'a> Slice3D<Output<'a> = [&'a f32; 3]> + 'static,
+    BLK: for<'a> Slice3D<Output<'a> = &'a FlowFlags> + 'static,
+{
+    if blockage.slice(c).is_all() {
+        return [0.0; 3];
+    }
+    // derivative of a velocity component along an axis, exterior of open
+    // faces holds the boundary velocity
+    let d = |component: usize, axis: usize| {
+        difference(c, axis, blockage, &bounds[component], |n| match n {
+            Ok(n) => Some(*vel.slice(&n)[component]),
+            Err(Edge::Fixed(v)) => Some(v),
+            Err(_) => None,
+        })
+    };
+    [d(2, 1) - d(1, 2), d(0, 2) - d(2, 0), d(1, 0) - d(0, 1)]
+}
+
+/// Vorticity confinement `force * (N x curl)`, where `N` is the normalized
+/// gradient of the curl magnitude pointing towards vortex cores.
+pub fn confine_vorticity<DST, CURL, BLK>(
+    vel: [&mut DST; 3],
+    curl: &CURL,
+    blockage: &BLK,
+    bounds: &Bounds,
+    force: f32,
+) where
+    DST: Flat3D<Item = f32>,
+    CURL: for<'a> Slice3D<Output<'a> = &'a [f32; 3]> + Sync + 'static,
+    BLK: for<'a> Slice3D<Output<'a> = &'a FlowFlags> + Sync + 'static,
+{
+    let magnitude = |w: &[f32; 3]| (w[0] * w[0] + w[1] * w[1] + w[2] * w[2]).sqrt();
+    let confinement = |c: &Coords| -> Option<[f32; 3]> {
+        if blockage.slice(c).is_all() {
+            return None;
+        }
+        let eta: [f32; 3] = std::array::from_fn(|axis| {
+            difference(c, axis, blockage, bounds, |n| {
+                n.ok().map(|n| magnitude(curl.slice(&n)))
+            })
+        });
+        let length = magnitude(&eta);
+        if length <= f32::EPSILON {
+            return None;
+        }
+        let n = eta.map(|v| v / length);
+        let w = curl.slice(c);
+        Some([
+            force * (n[1] * w[2] - n[2] * w[1]),
+            force * (n[2] * w[0] - n[0] * w[2]),
+            force * (n[0] * w[1] - n[1] * w[0]),
+        ])
+    };
+    parallel::for_each_mut3(vel, |c, v| {
+        if let Some(f) = confinement(c) {
+            for (v, f) in v.into_iter().zip(f) {
+                *v += f;
+            }
+        }
+    });
+}
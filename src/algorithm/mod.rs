@@ -1,3 +1,8 @@
 pub mod advection;
+pub mod boundary;
 pub mod diffusion;
 pub mod forces;
+pub mod projection;
+pub mod sampling;
+pub mod semi_lagrangian;
+pub mod trace;
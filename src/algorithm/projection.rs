@@ -0,0 +1,199 @@
+use rayon::prelude::*;
+
+use crate::{
+    data::flow::{FlowFlags, FACES},
+    math::{parallel, Coords, Flat3D, Slice3D},
+};
+
+use super::boundary::{Bounds, Edge};
+
+/// Scratch arrays of the projection solver. `phi` keeps the solution between
+/// steps as a starting guess for the next one.
+pub(crate) struct ProjectionTemp<A> {
+    phi: A,
+    residual: A,
+    direction: A,
+    product: A,
+    gradient: [A; 3],
+}
+
+impl<A: Clone> ProjectionTemp<A> {
+    pub fn new(init: A) -> Self {
+        Self {
+            phi: init.clone(),
+            residual: init.clone(),
+            direction: init.clone(),
+            product: init.clone(),
+            gradient: std::array::from_fn(|_| init.clone()),
+        }
+    }
+}
+
+/// Makes velocity divergence-free by subtracting the gradient of a pressure
+/// solved with conjugate gradient. Stops after `iterations` or once the
+/// root-mean-square divergence left falls to `tolerance`.
+pub(crate) fn project<A, BLK>(
+    vel: [&mut A; 3],
+    blockage: &BLK,
+    bounds: &[Bounds; 3],
+    temp: &mut ProjectionTemp<A>,
+    iterations: usize,
+    tolerance: f32,
+) where
+    A: Flat3D<Item = f32> + for<'a> Slice3D<Output<'a> = &'a f32> + Sync + 'static,
+    BLK: for<'a> Slice3D<Output<'a> = &'a FlowFlags> + Sync + 'static,
+{
+    let [vx, vy, vz] = vel;
+    let topology = &bounds[0];
+    let homogeneous = bounds.map(|b| b.homogeneous());
+    let ProjectionTemp {
+        phi,
+        residual,
+        direction,
+        product,
+        gradient,
+    } = temp;
+    let cells = phi.flat().len() as f32;
+
+    // Solving -D(G(phi)) = -D(v), residual starts from the previous solution
+    divergence(residual, [&*vx, &*vy, &*vz], blockage, bounds);
+    operator(product, phi, gradient, blockage, topology, &homogeneous);
+    residual
+        .flat_mut()
+        .par_iter_mut()
+        .zip(product.flat().par_iter())
+        .for_each(|(r, a)| *r = -*r - a);
+    direction.flat_mut().copy_from_slice(residual.flat());
+    let mut rr = parallel::dot(residual, residual);
+
+    for _ in 0..iterations {
+        if (rr / cells).sqrt() <= tolerance {
+            break;
+        }
+        operator(
+            product,
+            direction,
+            gradient,
+            blockage,
+            topology,
+            &homogeneous,
+        );
+        let curvature = parallel::dot(direction, product);
+        if curvature <= 0.0 {
+            break;
+        }
+        let alpha = rr / curvature;
+        phi.flat_mut()
+            .par_iter_mut()
+            .zip(direction.flat().par_iter())
+            .for_each(|(x, p)| *x += alpha * p);
+        residual
+            .flat_mut()
+            .par_iter_mut()
+            .zip(product.flat().par_iter())
+            .for_each(|(r, a)| *r -= alpha * a);
+        let next = parallel::dot(residual, residual);
+        let beta = next / rr;
+        rr = next;
+        direction
+            .flat_mut()
+            .par_iter_mut()
+            .zip(residual.flat().par_iter())
+            .for_each(|(p, r)| *p = r + beta * *p);
+    }
+
+    let [gx, gy, gz] = gradient;
+    pressure_gradient([gx, gy, gz], phi, blockage, topology);
+    for (v, g) in [vx, vy, vz].into_iter().zip([&*gx, &*gy, &*gz]) {
+        v.flat_mut()
+            .par_iter_mut()
+            .zip(g.flat().par_iter())
+            .for_each(|(v, g)| *v -= g);
+    }
+}
+
+/// Applies `-D(G(src))` into `dst`.
+fn operator<A, BLK>(
+    dst: &mut A,
+    src: &A,
+    gradient: &mut [A; 3],
+    blockage: &BLK,
+    topology: &Bounds,
+    homogeneous: &[Bounds; 3],
+) where
+    A: Flat3D<Item = f32> + for<'a> Slice3D<Output<'a> = &'a f32> + Sync + 'static,
+    BLK: for<'a> Slice3D<Output<'a> = &'a FlowFlags> + Sync + 'static,
+{
+    let [gx, gy, gz] = gradient;
+    pressure_gradient([gx, gy, gz], src, blockage, topology);
+    divergence(dst, [&*gx, &*gy, &*gz], blockage, homogeneous);
+    parallel::for_each_mut(dst, |_, v| *v = -*v);
+}
+
+/// Net outflow of every cell, velocity at a face is the average of the two
+/// cells sharing it. Nothing flows through blocked faces.
+fn divergence<DST, SRC, BLK>(dst: &mut DST, vel: [&SRC; 3], blockage: &BLK, bounds: &[Bounds; 3])
+where
+    DST: Flat3D<Item = f32>,
+    SRC: for<'a> Slice3D<Output<'a> = &'a f32> + Sync + 'static,
+    BLK: for<'a> Slice3D<Output<'a> = &'a FlowFlags> + Sync + 'static,
+{
+    parallel::for_each_mut(dst, |c, d| *d = divergence_at(c, vel, blockage, bounds));
+}
+
+/// Net outflow of a single cell, see [`divergence`].
+pub(crate) fn divergence_at<SRC, BLK>(
+    c: &Coords,
+    vel: [&SRC; 3],
+    blockage: &BLK,
+    bounds: &[Bounds; 3],
+) -> f32
+where
+    SRC: for<'a> Slice3D<Output<'a> = &'a f32> + 'static,
+    BLK: for<'a> Slice3D<Output<'a> = &'a FlowFlags> + 'static,
+{
+    let blk = *blockage.slice(c);
+    FACES.iter().enumerate().fold(0.0, |acc, (i, (face, _))| {
+        let axis = i % 3;
+        if blk.contains(*face) {
+            return acc;
+        }
+        let v = *vel[axis].slice(c);
+        let flux = match bounds[axis].neighbour(c, i) {
+            Ok(n) => 0.5 * (v + vel[axis].slice(&n)),
+            Err(Edge::Fixed(ext)) => 0.5 * (v + ext),
+            Err(_) => return acc,
+        };
+        if i < 3 {
+            acc + flux
+        } else {
+            acc - flux
+        }
+    })
+}
+
+/// Central difference of `phi`. Blocked and closed faces have zero gradient,
+/// open exterior is held at zero.
+fn pressure_gradient<DST, PHI, BLK>(dst: [&mut DST; 3], phi: &PHI, blockage: &BLK, bounds: &Bounds)
+where
+    DST: Flat3D<Item = f32>,
+    PHI: for<'a> Slice3D<Output<'a> = &'a f32> + Sync + 'static,
+    BLK: for<'a> Slice3D<Output<'a> = &'a FlowFlags> + Sync + 'static,
+{
+    let side = |c: &Coords, p: f32, face: usize| -> f32 {
+        if blockage.slice(c).contains(FACES[face].0) {
+            return p;
+        }
+        match bounds.neighbour(c, face) {
+            Ok(n) => *phi.slice(&n),
+            Err(Edge::Fixed(_)) => 0.0,
+            Err(_) => p,
+        }
+    };
+    for (axis, dst) in dst.into_iter().enumerate() {
+        parallel::for_each_mut(dst, |c, g| {
+            let p = *phi.slice(c);
+            *g = 0.5 * (side(c, p, axis) - side(c, p, axis + 3));
+        });
+    }
+}
@@ -0,0 +1,118 @@
+use crate::{
+    data::flow::FlowFlags,
+    math::{Coords, Slice3D},
+};
+
+use super::{
+    advection::AdvectionResult,
+    boundary::{Bounds, Edge},
+};
+
+// Corner index bit of each axis and the face crossed when moving along it
+const AXES: [(usize, FlowFlags); 3] = [
+    (1, FlowFlags::X_FORW),
+    (2, FlowFlags::Y_FORW),
+    (4, FlowFlags::Z_FORW),
+];
+
+/// Trilinear interpolation of `src` at `pos`, in cell units.
+///
+/// Position is kept inside closed edges and within a cell of open ones,
+/// corners behind open edges hold the exterior value. Corners that can not
+/// be reached from the corner nearest to `pos` without crossing a blocked
+/// face are left out and the rest weighted up, so values do not leak through
+/// walls.
+pub fn sample<SRC, BLK>(src: &SRC, blockage: &BLK, bounds: &Bounds, pos: [f32; 3]) -> f32
+where
+    SRC: for<'a> Slice3D<Output<'a> = &'a f32> + 'static,
+    BLK: for<'a> Slice3D<Output<'a> = &'a FlowFlags> + 'static,
+{
+    sample_range(src, blockage, bounds, pos).0
+}
+
+/// Same as [`sample`], also returns the lowest and the highest of the values
+/// taking part in the interpolation.
+pub fn sample_range<SRC, BLK>(
+    src: &SRC,
+    blockage: &BLK,
+    bounds: &Bounds,
+    pos: [f32; 3],
+) -> (f32, f32, f32)
+where
+    SRC: for<'a> Slice3D<Output<'a> = &'a f32> + 'static,
+    BLK: for<'a> Slice3D<Output<'a> = &'a FlowFlags> + 'static,
+{
+    let mut pos = pos;
+    bounds.confine(&mut pos);
+    bounds.enclose(&mut pos);
+    let k = AdvectionResult::at(pos);
+    let corners = k.corners().map(|d| bounds.resolve(d));
+    let nearest = AXES.iter().enumerate().fold(0, |n, (axis, (bit, _))| {
+        if pos[axis] - pos[axis].floor() >= 0.5 {
+            n | bit
+        } else {
+            n
+        }
+    });
+    let weights = k.weights();
+    let reachable = reachable(&corners, &weights, nearest, blockage);
+
+    let (mut sum, mut total) = (0.0, 0.0);
+    let (mut low, mut high) = (f32::INFINITY, f32::NEG_INFINITY);
+    for (i, (w, corner)) in weights.into_iter().zip(corners).enumerate() {
+        let v = match corner {
+            Ok(c) if reachable[i] => *src.slice(&c),
+            Err(Edge::Fixed(v)) => v,
+            _ => continue,
+        };
+        sum += w * v;
+        total += w;
+        if w > 0.0 {
+            low = low.min(v);
+            high = high.max(v);
+        }
+    }
+    if total <= f32::EPSILON {
+        return (0.0, 0.0, 0.0);
+    }
+    (sum / total, low, high)
+}
+
+/// Flood fill over the edges of the interpolation cube, only through corners
+/// that take part in the interpolation.
+fn reachable<BLK>(
+    corners: &[Result<Coords, Edge>; 8],
+    weights: &[f32; 8],
+    from: usize,
+    blockage: &BLK,
+) -> [bool; 8]
+where
+    BLK: for<'a> Slice3D<Output<'a> = &'a FlowFlags> + 'static,
+{
+    let mut reached = [false; 8];
+    if corners[from].is_err() {
+        // Sampling outside of the domain, nothing to cross
+        return corners.map(|c| c.is_ok());
+    }
+    reached[from] = true;
+    let mut stack = vec![from];
+    while let Some(i) = stack.pop() {
+        for (bit, face) in AXES {
+            let j = i ^ bit;
+            if reached[j] || weights[j] <= 0.0 {
+                continue;
+            }
+            // the face is stored on both cells, forward one is enough
+            let low = if i & bit == 0 { i } else { j };
+            let open = match (&corners[low], &corners[j]) {
+                (Ok(c), Ok(_)) => !blockage.slice(c).contains(face),
+                _ => false,
+            };
+            if open {
+                reached[j] = true;
+                stack.push(j);
+            }
+        }
+    }
+    reached
+}
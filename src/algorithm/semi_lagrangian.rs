@@ -0,0 +1,92 @@
+use crate::{
+    data::flow::FlowFlags,
+    math::{parallel, Flat3D, Sized3D, Slice3D},
+};
+
+use super::{boundary::Bounds, sampling, trace};
+
+/// Traces every cell centre along `vel` over `scale` cells per unit of
+/// velocity with a midpoint step, negative `scale` traces back. Paths stop
+/// at blocked faces, those leaving through an open edge end a cell outside of
+/// the domain and pick up the exterior value.
+pub(crate) fn trace_cells<DST, VEL, BLK>(
+    dst: &mut DST,
+    vel: [&VEL; 3],
+    blockage: &BLK,
+    bounds: &[Bounds; 3],
+    scale: f32,
+) where
+    DST: Flat3D<Item = [f32; 3]>,
+    VEL: for<'a> Slice3D<Output<'a> = &'a f32> + Sync + 'static,
+    BLK: Sized3D + for<'a> Slice3D<Output<'a> = &'a FlowFlags> + Sync + 'static,
+{
+    parallel::for_each_mut(dst, |c, end| {
+        let pos = [c.0 as f32, c.1 as f32, c.2 as f32];
+        let mid: [f32; 3] = std::array::from_fn(|i| pos[i] + 0.5 * scale * vel[i].slice(c));
+        let mut to = std::array::from_fn(|i| {
+            pos[i] + scale * sampling::sample(vel[i], blockage, &bounds[i], mid)
+        });
+        bounds[0].limit(pos, &mut to);
+        *end = trace::travel(blockage, &bounds[0], false, pos, to).unwrap_or(to);
+        // Any point behind an open edge samples the same exterior value
+        bounds[0].enclose(end);
+    });
+}
+
+/// Sets every cell of `dst` to `src` sampled at the departure point of the
+/// cell. Solid cells keep their value.
+pub(crate) fn advect<DST, SRC, POS, BLK>(
+    dst: &mut DST,
+    src: &SRC,
+    departure: &POS,
+    blockage: &BLK,
+    bounds: &Bounds,
+) where
+    DST: Flat3D<Item = f32>,
+    SRC: for<'a> Slice3D<Output<'a> = &'a f32> + Sync + 'static,
+    POS: for<'a> Slice3D<Output<'a> = &'a [f32; 3]> + Sync + 'static,
+    BLK: for<'a> Slice3D<Output<'a> = &'a FlowFlags> + Sync + 'static,
+{
+    parallel::for_each_mut(dst, |c, v| {
+        *v = if blockage.slice(c).is_all() {
+            *src.slice(c)
+        } else {
+            sampling::sample(src, blockage, bounds, *departure.slice(c))
+        };
+    });
+}
+
+/// MacCormack correction of `advected`, the result of [`advect`] on `src`.
+/// Advecting the result back along `arrival` estimates the error, half of it
+/// is compensated. Cells where that would leave the range of the values the
+/// departure point was interpolated from keep the uncorrected value.
+pub(crate) fn correct<DST, SRC, POS, BLK>(
+    dst: &mut DST,
+    src: &SRC,
+    advected: &SRC,
+    departure: &POS,
+    arrival: &POS,
+    blockage: &BLK,
+    bounds: &Bounds,
+) where
+    DST: Flat3D<Item = f32>,
+    SRC: for<'a> Slice3D<Output<'a> = &'a f32> + Sync + 'static,
+    POS: for<'a> Slice3D<Output<'a> = &'a [f32; 3]> + Sync + 'static,
+    BLK: for<'a> Slice3D<Output<'a> = &'a FlowFlags> + Sync + 'static,
+{
+    parallel::for_each_mut(dst, |c, v| {
+        let forward = *advected.slice(c);
+        if blockage.slice(c).is_all() {
+            *v = forward;
+            return;
+        }
+        let back = sampling::sample(advected, blockage, bounds, *arrival.slice(c));
+        let (_, low, high) = sampling::sample_range(src, blockage, bounds, *departure.slice(c));
+        let corrected = forward + 0.5 * (src.slice(c) - back);
+        *v = if (low..=high).contains(&corrected) {
+            corrected
+        } else {
+            forward
+        };
+    });
+}
@@ -0,0 +1,64 @@
+use crate::{
+    data::flow::{FlowFlags, FACES},
+    math::{CoordsDiff, Sized3D, Slice3D},
+};
+
+use super::boundary::{Bounds, Edge};
+
+fn cell_index(v: f32) -> isize {
+    (v + 0.5).floor() as isize
+}
+
+/// Moves from `from` to `to` in cell units one axis at a time, either
+/// mirroring about or stopping at every blocked face and closed edge on the
+/// way. Periodic axes are wrapped back into the domain. Returns `None` once
+/// the path leaves the domain through an open edge or starts outside of it.
+pub fn travel<BLK>(
+    blockage: &BLK,
+    bounds: &Bounds,
+    bounce: bool,
+    from: [f32; 3],
+    to: [f32; 3],
+) -> Option<[f32; 3]>
+where
+    BLK: Sized3D + for<'a> Slice3D<Output<'a> = &'a FlowFlags>,
+{
+    let size = blockage.size();
+    let size = [size.0, size.1, size.2];
+    let mut pos = from;
+    for axis in 0..3 {
+        let mut cell = pos.map(cell_index);
+        let mut target = to[axis];
+        loop {
+            let next = cell_index(target);
+            if next == cell[axis] {
+                break;
+            }
+            let step = if next > cell[axis] { 1 } else { -1 };
+            let face = if step > 0 { axis } else { axis + 3 };
+            let here = bounds.resolve(CoordsDiff(cell[0], cell[1], cell[2])).ok()?;
+            let blocked = match bounds.neighbour(&here, face) {
+                Ok(_) => blockage.slice(&here).contains(FACES[face].0),
+                Err(Edge::Closed) => true,
+                Err(_) => return None,
+            };
+            if !blocked {
+                cell[axis] += step;
+                continue;
+            }
+            let plane = cell[axis] as f32 + 0.5 * step as f32;
+            let mirrored = 2.0 * plane - target;
+            // A target right on the face mirrors onto itself, so it rests
+            // against the face instead
+            target = if bounce && cell_index(mirrored) != next {
+                mirrored
+            } else {
+                plane - 1e-3 * step as f32
+            };
+        }
+        // Only periodic axes let the cell run past the domain
+        let len = size[axis] as isize;
+        pos[axis] = target - (cell[axis].div_euclid(len) * len) as f32;
+    }
+    Some(pos)
+}
@@ -0,0 +1,90 @@
+use crate::{
+    math::{grid::Grid, Slice3D, Slice3DMut},
+    Coords, FlowFlags, GridDomain, Sized3D,
+};
+
+use super::{emitter::Shape, flow::FACES};
+
+impl<const P_SIZE: usize, G: Grid> GridDomain<P_SIZE, G> {
+    /// Faces of the cell that do not let anything through.
+    pub fn blocked_faces(&self, c: &Coords) -> FlowFlags {
+        *self.data.blockage.slice(c)
+    }
+
+    /// Cell is solid when every one of its faces is blocked.
+    pub fn is_solid(&self, c: &Coords) -> bool {
+        self.blocked_faces(c).is_all()
+    }
+
+    /// Places or removes a wall between two face-adjacent cells.
+    ///
+    /// # Panics
+    ///
+    /// When the cells do not share a face or lie outside of the grid, the
+    /// periodic seam does not count as shared.
+    pub fn set_wall(&mut self, a: &Coords, b: &Coords, blocked: bool) {
+        let size = self.size();
+        let (face, _) = FACES
+            .into_iter()
+            .find(|(_, d)| a.checked_offset(*d, size) == Some(*b))
+            .expect("cells must share a face");
+        self.data.blockage.slice_mut(a).set(face, blocked);
+        self.data
+            .blockage
+            .slice_mut(b)
+            .set(face.opposite(), blocked);
+    }
+
+    /// Turns the cell into an obstacle, or opens it back up.
+    ///
+    /// Faces shared with solid neighbours stay blocked when a cell is cleared.
+    pub fn set_solid(&mut self, c: &Coords, solid: bool) {
+        let size = self.size();
+        for (face, d) in FACES {
+            match c.checked_offset(d, size) {
+                Some(n) => {
+                    let blocked = solid || self.is_solid(&n);
+                    self.data.blockage.slice_mut(c).set(face, blocked);
+                    self.data
+                        .blockage
+                        .slice_mut(&n)
+                        .set(face.opposite(), blocked);
+                }
+                None => self.data.blockage.slice_mut(c).set(face, solid),
+            }
+        }
+    }
+
+    /// Blocks the given faces of the cell, along with the matching faces of
+    /// its neighbours. Blocking every face makes the cell solid.
+    pub fn block_faces(&mut self, c: &Coords, faces: FlowFlags) {
+        let size = self.size();
+        for (face, d) in FACES.into_iter().filter(|(f, _)| faces.contains(*f)) {
+            self.data.blockage.slice_mut(c).insert(face);
+            if let Some(n) = c.checked_offset(d, size) {
+                self.data.blockage.slice_mut(&n).insert(face.opposite());
+            }
+        }
+    }
+
+    /// Fills every cell of the shape.
+    pub fn fill(&mut self, shape: &Shape, solid: bool) {
+        for c in shape.cells(self.size()) {
+            self.set_solid(&c, solid);
+        }
+    }
+
+    /// Fills cells in `from..to` (`to` exclusive on every axis).
+    pub fn fill_box(&mut self, from: &Coords, to: &Coords, solid: bool) {
+        let shape = Shape::Box {
+            from: *from,
+            to: *to,
+        };
+        self.fill(&shape, solid);
+    }
+
+    /// Fills cells whose centres lie within `radius` of `center`, in cell units.
+    pub fn fill_sphere(&mut self, center: [f32; 3], radius: f32, solid: bool) {
+        self.fill(&Shape::Sphere { center, radius }, solid);
+    }
+}
@@ -0,0 +1,202 @@
+use crate::{
+    data::emitter::{Emitter, Shape, Sink},
+    math::{
+        grid::{DynGrid, FixedGrid, Grid},
+        swapchain::Swapable,
+    },
+    Boundaries, Boundary, Coords, Domain, DomainError, DomainProperties, DynDomain, FlowFlags,
+    GridDomain, Sized3D,
+};
+
+/// Sets up a domain, checking the configuration once before it is built.
+pub struct DomainBuilder<const P_SIZE: usize, G: Grid> {
+    grid: G,
+    prop: DomainProperties,
+    blockage: Vec<(Shape, FlowFlags)>,
+    emitters: Vec<Emitter>,
+    sinks: Vec<Sink>,
+    pressure: Vec<(Coords, [f32; P_SIZE])>,
+    velocity: Vec<(Coords, (f32, f32, f32))>,
+}
+
+impl<const P_SIZE: usize, const X: usize, const Y: usize, const Z: usize> Domain<P_SIZE, X, Y, Z> {
+    pub fn builder() -> DomainBuilder<P_SIZE, FixedGrid<X, Y, Z>> {
+        DomainBuilder::new(FixedGrid)
+    }
+}
+
+impl<const P_SIZE: usize> DynDomain<P_SIZE> {
+    pub fn builder(size: Coords) -> DomainBuilder<P_SIZE, DynGrid> {
+        DomainBuilder::new(DynGrid(size))
+    }
+}
+
+impl<const P_SIZE: usize, G: Grid> DomainBuilder<P_SIZE, G> {
+    pub fn new(grid: G) -> Self {
+        Self {
+            grid,
+            prop: Default::default(),
+            blockage: Vec::new(),
+            emitters: Vec::new(),
+            sinks: Vec::new(),
+            pressure: Vec::new(),
+            velocity: Vec::new(),
+        }
+    }
+
+    /// Replaces every property, boundaries included.
+    pub fn properties(mut self, prop: DomainProperties) -> Self {
+        self.prop = prop;
+        self
+    }
+
+    pub fn boundaries(mut self, boundaries: Boundaries) -> Self {
+        self.prop.boundaries = boundaries;
+        self
+    }
+
+    /// Fills every cell of the shape with solid.
+    pub fn solid(self, shape: Shape) -> Self {
+        self.blocked(shape, FlowFlags::all())
+    }
+
+    /// Blocks the given faces of every cell of the shape, see
+    /// [`GridDomain::block_faces`].
+    pub fn blocked(mut self, shape: Shape, faces: FlowFlags) -> Self {
+        self.blockage.push((shape, faces));
+        self
+    }
+
+    pub fn emitter(mut self, emitter: Emitter) -> Self {
+        self.emitters.push(emitter);
+        self
+    }
+
+    pub fn sink(mut self, sink: Sink) -> Self {
+        self.sinks.push(sink);
+        self
+    }
+
+    /// Initial pressure of a cell, published as soon as the domain is built.
+    pub fn pressure(mut self, c: Coords, v: [f32; P_SIZE]) -> Self {
+        self.pressure.push((c, v));
+        self
+    }
+
+    /// Initial velocity of a cell, see [`Self::pressure`].
+    pub fn velocity(mut self, c: Coords, v: (f32, f32, f32)) -> Self {
+        self.velocity.push((c, v));
+        self
+    }
+
+    /// Checks the configuration and builds the domain. Besides
+    /// [`DomainProperties::validate`] it rejects cells outside of the grid,
+    /// periodic faces without their opposite, and inflow, buoyancy, emitters
+    /// or sinks using missing pressure channels.
+    pub fn build(self) -> Result<GridDomain<P_SIZE, G>, DomainError> {
+        check_properties::<P_SIZE>(&self.prop)?;
+        check_boundaries::<P_SIZE>(&self.prop.boundaries)?;
+        let mut domain = GridDomain::with_grid(self.grid, self.prop);
+        let size = domain.size();
+        let cells = self.pressure.iter().map(|(c, _)| c);
+        for c in cells.chain(self.velocity.iter().map(|(c, _)| c)) {
+            domain.check_coords(c)?;
+        }
+        let channels = self.emitters.iter().filter_map(|e| e.pressure);
+        let channels = channels.map(|(channel, _)| ("emitters", channel));
+        for (property, channel) in channels.chain(self.sinks.iter().map(|s| ("sinks", s.channel))) {
+            if channel >= P_SIZE {
+                return Err(DomainError::InvalidProperty {
+                    property: property.into(),
+                    reason: "names a missing pressure channel",
+                });
+            }
+        }
+        for (shape, faces) in &self.blockage {
+            check_shape(shape, size)?;
+            for c in shape.cells(size) {
+                domain.block_faces(&c, *faces);
+            }
+        }
+        for emitter in self.emitters {
+            domain.add_emitter(emitter)?;
+        }
+        for sink in self.sinks {
+            domain.add_sink(sink)?;
+        }
+        for (c, v) in &self.pressure {
+            domain.set_pressure(c, v);
+        }
+        for (c, v) in &self.velocity {
+            domain.set_velocity(c, *v);
+        }
+        domain.data.pressure.swap_buffers();
+        domain.data.velocity.swap_buffers();
+        domain.config = Some(domain.prop.clone());
+        Ok(domain)
+    }
+}
+
+/// Errors when a point or box reaches outside of a grid of `size`. Spheres
+/// may be cut off by the grid.
+pub(crate) fn check_shape(shape: &Shape, size: Coords) -> Result<(), DomainError> {
+    let outside = match shape {
+        Shape::Point(c) => Some(*c).filter(|c| c.0 >= size.0 || c.1 >= size.1 || c.2 >= size.2),
+        Shape::Box { from, to } => [*from, *to]
+            .into_iter()
+            .find(|c| c.0 > size.0 || c.1 > size.1 || c.2 > size.2),
+        Shape::Sphere { .. } => None,
+    };
+    match outside {
+        Some(coords) => Err(DomainError::OutOfBounds { coords, size }),
+        None => Ok(()),
+    }
+}
+
+/// [`DomainProperties::validate`] along with checks that need the number of
+/// pressure channels.
+pub(crate) fn check_properties<const P_SIZE: usize>(
+    prop: &DomainProperties,
+) -> Result<(), DomainError> {
+    prop.validate()?;
+    if let Some(b) = &prop.buoyancy {
+        for (property, channel) in [("temperature", b.temperature), ("density", b.density)] {
+            if channel >= P_SIZE {
+                return Err(DomainError::InvalidProperty {
+                    property: format!("buoyancy.{}", property),
+                    reason: "names a missing pressure channel",
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+fn check_boundaries<const P_SIZE: usize>(b: &Boundaries) -> Result<(), DomainError> {
+    let faces = [
+        ("x_forw", &b.x_forw, &b.x_back),
+        ("y_forw", &b.y_forw, &b.y_back),
+        ("z_forw", &b.z_forw, &b.z_back),
+        ("x_back", &b.x_back, &b.x_forw),
+        ("y_back", &b.y_back, &b.y_forw),
+        ("z_back", &b.z_back, &b.z_forw),
+    ];
+    for (name, face, opposite) in faces {
+        let invalid = |reason| {
+            Err(DomainError::InvalidProperty {
+                property: format!("boundaries.{}", name),
+                reason,
+            })
+        };
+        match face {
+            Boundary::Periodic if *opposite != Boundary::Periodic => {
+                return invalid("periodic face needs a periodic opposite face");
+            }
+            Boundary::Inflow { pressure, .. } if pressure.len() > P_SIZE => {
+                return invalid("inflow pressure has more channels than the domain");
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,168 @@
+use crate::{
+    data::{
+        builder::check_shape,
+        emitter::{EmitterHandle, Shape},
+    },
+    math::grid::Grid,
+    Coords, DomainError, DomainProperties, GridDomain, Pid, Sized3D,
+};
+
+/// Quantity a [`Controller`] holds at its setpoint, read from the published
+/// fields before each step.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Measure {
+    /// Sum of a pressure channel over the cells of a region.
+    Mass { channel: usize, shape: Shape },
+    /// Mean of a velocity component over the open cells of a region.
+    Velocity { axis: usize, shape: Shape },
+    /// Pressure channel of a single cell.
+    Pressure { channel: usize, cell: Coords },
+}
+
+/// Value a [`Controller`] drives.
+#[derive(Clone, Debug)]
+pub enum Actuator {
+    /// Property picked by the accessor, e.g.
+    /// `|p| p.vorticity.get_or_insert(0.0)`.
+    Property(fn(&mut DomainProperties) -> &mut f32),
+    /// Pressure rate of an emitter.
+    EmitterPressure(EmitterHandle),
+    /// Acceleration of an emitter along an axis.
+    EmitterVelocity { handle: EmitterHandle, axis: usize },
+    /// Drain rate of a sink.
+    SinkRate(EmitterHandle),
+}
+
+/// Holds a measured quantity at `setpoint` by nudging the actuator with the
+/// output of `pid` every step. Raising the actuator is expected to raise the
+/// measure, use negative gains otherwise.
+#[derive(Clone, Debug)]
+pub struct Controller {
+    pub measure: Measure,
+    pub actuator: Actuator,
+    pub setpoint: f32,
+    pub pid: Pid,
+    /// Range the actuator is kept in.
+    pub limits: (f32, f32),
+}
+
+/// Identifies a controller attached to a domain.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ControllerHandle(u64);
+
+/// Controllers, run in order of addition.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Controllers {
+    next: u64,
+    list: Vec<(ControllerHandle, Controller)>,
+}
+
+impl<const P_SIZE: usize, G: Grid> GridDomain<P_SIZE, G> {
+    /// Errors when the measure names a missing channel or axis, reaches
+    /// outside of the domain, or the limits are reversed or NaN.
+    pub fn add_controller(
+        &mut self,
+        controller: Controller,
+    ) -> Result<ControllerHandle, DomainError> {
+        let invalid = |property: &str, reason| {
+            Err(DomainError::InvalidProperty {
+                property: property.into(),
+                reason,
+            })
+        };
+        match &controller.measure {
+            Measure::Mass { channel, .. } | Measure::Pressure { channel, .. }
+                if *channel >= P_SIZE =>
+            {
+                return invalid("measure.channel", "names a missing pressure channel");
+            }
+            Measure::Velocity { axis, .. } if *axis >= 3 => {
+                return invalid("measure.axis", "must be below 3");
+            }
+            Measure::Mass { shape, .. } | Measure::Velocity { shape, .. } => {
+                check_shape(shape, self.size())?
+            }
+            Measure::Pressure { cell, .. } => self.check_coords(cell)?,
+        }
+        let (low, high) = controller.limits;
+        if low.is_nan() || high.is_nan() || low > high {
+            return invalid("limits", "must be ordered and not NaN");
+        }
+        let c = &mut self.controllers;
+        let handle = ControllerHandle(c.next);
+        c.next += 1;
+        c.list.push((handle, controller));
+        Ok(handle)
+    }
+
+    /// Detaches controller, returns false if it was already removed. The
+    /// driven value keeps its last setting.
+    pub fn remove_controller(&mut self, handle: ControllerHandle) -> bool {
+        let list = &mut self.controllers.list;
+        let len = list.len();
+        list.retain(|(h, _)| *h != handle);
+        list.len() != len
+    }
+
+    /// Attached controller, to move its setpoint or retune it.
+    pub fn controller_mut(&mut self, handle: ControllerHandle) -> Option<&mut Controller> {
+        let list = &mut self.controllers.list;
+        list.iter_mut().find(|(h, _)| *h == handle).map(|(_, c)| c)
+    }
+
+    /// Current value of the controller's measure.
+    pub fn measure(&self, measure: &Measure) -> f32 {
+        let size = self.size();
+        match measure {
+            Measure::Mass { channel, shape } => {
+                shape.cells(size).map(|c| self.pressure(&c)[*channel]).sum()
+            }
+            Measure::Velocity { axis, shape } => {
+                let (total, count) = shape
+                    .cells(size)
+                    .filter(|c| !self.is_solid(c))
+                    .map(|c| {
+                        let (x, y, z) = self.velocity(&c);
+                        [x, y, z][*axis]
+                    })
+                    .fold((0.0, 0), |(t, n), v| (t + v, n + 1));
+                total / count.max(1) as f32
+            }
+            Measure::Pressure { channel, cell } => self.pressure(cell)[*channel],
+        }
+    }
+
+    /// Updates actuators from the published fields, a zero timestep leaves
+    /// them alone.
+    pub(crate) fn sim_controllers(&mut self) {
+        let dt = self.prop.step_delta_time;
+        if dt <= 0.0 {
+            return;
+        }
+        let mut list = std::mem::take(&mut self.controllers.list);
+        for (_, c) in &mut list {
+            let measured = self.measure(&c.measure);
+            let Some(value) = self.actuator_mut(&c.actuator) else {
+                continue;
+            };
+            let (low, high) = c.limits;
+            *value = (*value + c.pid.val(measured, c.setpoint, dt)).clamp(low, high);
+        }
+        self.controllers.list = list;
+    }
+
+    fn actuator_mut(&mut self, actuator: &Actuator) -> Option<&mut f32> {
+        match actuator {
+            Actuator::Property(field) => Some(field(&mut self.prop)),
+            Actuator::EmitterPressure(handle) => {
+                let emitter = self.emitters.emitter_mut(*handle)?;
+                emitter.pressure.as_mut().map(|(_, rate)| rate)
+            }
+            Actuator::EmitterVelocity { handle, axis } => {
+                let emitter = self.emitters.emitter_mut(*handle)?;
+                emitter.velocity.get_or_insert([0.0; 3]).get_mut(*axis)
+            }
+            Actuator::SinkRate(handle) => Some(&mut self.emitters.sink_mut(*handle)?.rate),
+        }
+    }
+}
@@ -0,0 +1,150 @@
+use rayon::prelude::*;
+
+use crate::{
+    algorithm::{boundary::Bounds, forces, projection},
+    math::{grid::Grid, Slice3D},
+    Coords, GridDomain, Sized3D,
+};
+
+/// Statistics of a single pressure channel over its finite values.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ChannelStats {
+    pub total: f32,
+    pub min: f32,
+    pub max: f32,
+    pub mean: f32,
+}
+
+/// Summary of the published fields, see [`GridDomain::diagnostics`].
+/// Velocity derived values are in world units.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Diagnostics<const P_SIZE: usize> {
+    pub pressure: [ChannelStats; P_SIZE],
+    pub max_velocity: f32,
+    /// Mean of half the squared velocity per cell.
+    pub kinetic_energy: f32,
+    /// Sum of the absolute divergence of every cell.
+    pub divergence: f32,
+    /// Sum of half the squared curl of every cell.
+    pub enstrophy: f32,
+    /// Cells holding NaN in any field.
+    pub nan_cells: usize,
+    /// Cells holding an infinity in any field.
+    pub infinite_cells: usize,
+}
+
+// Sums of a single z-slab
+#[derive(Clone, Copy)]
+struct Partial<const P_SIZE: usize> {
+    // total, min, max and number of finite values
+    pressure: [(f32, f32, f32, usize); P_SIZE],
+    max_speed: f32,
+    energy: f32,
+    divergence: f32,
+    enstrophy: f32,
+    nan_cells: usize,
+    infinite_cells: usize,
+}
+
+impl<const P_SIZE: usize> Partial<P_SIZE> {
+    fn new() -> Self {
+        Self {
+            pressure: [(0.0, f32::INFINITY, f32::NEG_INFINITY, 0); P_SIZE],
+            max_speed: 0.0,
+            energy: 0.0,
+            divergence: 0.0,
+            enstrophy: 0.0,
+            nan_cells: 0,
+            infinite_cells: 0,
+        }
+    }
+
+    fn merge(mut self, other: Self) -> Self {
+        for (a, b) in self.pressure.iter_mut().zip(other.pressure) {
+            *a = (a.0 + b.0, a.1.min(b.1), a.2.max(b.2), a.3 + b.3);
+        }
+        self.max_speed = self.max_speed.max(other.max_speed);
+        self.energy += other.energy;
+        self.divergence += other.divergence;
+        self.enstrophy += other.enstrophy;
+        self.nan_cells += other.nan_cells;
+        self.infinite_cells += other.infinite_cells;
+        self
+    }
+}
+
+impl<const P_SIZE: usize, G: Grid> GridDomain<P_SIZE, G> {
+    /// Statistics of the published fields in a single parallel pass. Slabs
+    /// are summed in order, so results do not depend on the number of
+    /// threads. Non-finite values are only counted, they are left out of
+    /// every other figure.
+    pub fn diagnostics(&self) -> Diagnostics<P_SIZE> {
+        let size = self.size();
+        let Coords(x, y, z) = size;
+        let h = self.prop.cell_size;
+        let bounds: [Bounds; 3] =
+            std::array::from_fn(|i| Bounds::velocity(&self.prop.boundaries, size, i));
+        let velocity = &self.data.velocity;
+        let chains = velocity.chains().each_ref();
+        let blockage = &self.data.blockage;
+
+        let slabs: Vec<Partial<P_SIZE>> = (0..z)
+            .into_par_iter()
+            .map(|z| {
+                let mut p = Partial::new();
+                for c in (0..y).flat_map(|y| (0..x).map(move |x| Coords(x, y, z))) {
+                    let pressure = self.data.pressure.slice(&c);
+                    let vel = velocity.slice(&c).map(|v| *v);
+                    let values = || pressure.iter().map(|v| **v).chain(vel);
+                    if values().any(f32::is_nan) {
+                        p.nan_cells += 1;
+                    } else if values().any(f32::is_infinite) {
+                        p.infinite_cells += 1;
+                    }
+                    for (s, v) in p.pressure.iter_mut().zip(pressure) {
+                        if v.is_finite() {
+                            *s = (s.0 + v, s.1.min(*v), s.2.max(*v), s.3 + 1);
+                        }
+                    }
+                    let speed2 = vel.iter().map(|v| v * v).sum::<f32>();
+                    if speed2.is_finite() {
+                        p.max_speed = p.max_speed.max(speed2);
+                        p.energy += 0.5 * speed2;
+                    }
+                    let div = projection::divergence_at(&c, chains, blockage, &bounds);
+                    if div.is_finite() {
+                        p.divergence += div.abs() / h;
+                    }
+                    let curl = forces::curl_at(&c, velocity, blockage, &bounds);
+                    let curl2 = curl.iter().map(|w| w * w).sum::<f32>() / (h * h);
+                    if curl2.is_finite() {
+                        p.enstrophy += 0.5 * curl2;
+                    }
+                }
+                p
+            })
+            .collect();
+        let total = slabs.into_iter().fold(Partial::new(), Partial::merge);
+
+        let pressure = total.pressure.map(|(total, min, max, count)| {
+            if count == 0 {
+                return ChannelStats::default();
+            }
+            ChannelStats {
+                total,
+                min,
+                max,
+                mean: total / count as f32,
+            }
+        });
+        Diagnostics {
+            pressure,
+            max_velocity: total.max_speed.sqrt(),
+            kinetic_energy: total.energy / (x * y * z).max(1) as f32,
+            divergence: total.divergence,
+            enstrophy: total.enstrophy,
+            nan_cells: total.nan_cells,
+            infinite_cells: total.infinite_cells,
+        }
+    }
+}
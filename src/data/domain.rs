@@ -1,37 +1,97 @@
 use crate::{
-    algorithm::{advection, diffusion, forces}, data::runtime::{DomainRuntime, DomainTemp}, iterator, math::{swapchain::Swapable, Sized3D, Slice3D, Slice3DMut}, Coords, DomainProperties
+    algorithm::{
+        advection, boundary::Bounds, diffusion, forces, projection, sampling, semi_lagrangian,
+    },
+    data::{
+        builder::check_properties,
+        control::Controllers,
+        emitter::Emitters,
+        probes::Probes,
+        reload::Transition,
+        runtime::{DomainRuntime, DomainTemp, Traces},
+        validation::{NonFiniteError, Pass},
+    },
+    math::{
+        grid::{DynGrid, FixedGrid, Grid},
+        swapchain::Swapable,
+        Sized3D, Slice3D, Slice3DMut,
+    },
+    AdvectionScheme, Coords, DomainError, DomainProperties, FlowFlags, Recovery, VorticityMode,
 };
 
-#[derive(Default)]
-pub struct Domain<const P_SIZE: usize, const X: usize, const Y: usize, const Z: usize> {
-    pub data: DomainRuntime<P_SIZE, X, Y, Z>,
-    temp: DomainTemp<X, Y, Z>,
+/// Simulation domain over any [`Grid`].
+pub struct GridDomain<const P_SIZE: usize, G: Grid> {
+    pub data: DomainRuntime<P_SIZE, G>,
+    pub(crate) temp: DomainTemp<G>,
     pub prop: DomainProperties,
+    pub(crate) emitters: Emitters,
+    pub(crate) controllers: Controllers,
+    pub(crate) probes: Probes<P_SIZE>,
+    pub(crate) config: Option<DomainProperties>,
+    pub(crate) transition: Option<Transition>,
+    grid: G,
 }
 
-impl<const P_SIZE: usize, const X: usize, const Y: usize, const Z: usize> Sized3D
-    for Domain<P_SIZE, X, Y, Z>
-{
+/// Domain with grid size fixed at compile time.
+pub type Domain<const P_SIZE: usize, const X: usize, const Y: usize, const Z: usize> =
+    GridDomain<P_SIZE, FixedGrid<X, Y, Z>>;
+
+/// Domain with grid size chosen at construction.
+pub type DynDomain<const P_SIZE: usize> = GridDomain<P_SIZE, DynGrid>;
+
+impl<const P_SIZE: usize, G: Grid + Default> Default for GridDomain<P_SIZE, G> {
+    fn default() -> Self {
+        Self::with_grid(Default::default(), Default::default())
+    }
+}
+
+impl<const P_SIZE: usize, G: Grid> Sized3D for GridDomain<P_SIZE, G> {
     fn size(&self) -> Coords {
-        Coords(X, Y, Z)
+        self.grid.size()
     }
 }
 
 impl<const P_SIZE: usize, const X: usize, const Y: usize, const Z: usize> Domain<P_SIZE, X, Y, Z> {
     pub fn new(prop: DomainProperties) -> Self {
+        Self::with_grid(FixedGrid, prop)
+    }
+}
+
+impl<const P_SIZE: usize> DynDomain<P_SIZE> {
+    pub fn new(size: Coords, prop: DomainProperties) -> Self {
+        Self::with_grid(DynGrid(size), prop)
+    }
+}
+
+impl<const P_SIZE: usize, G: Grid> GridDomain<P_SIZE, G> {
+    pub fn with_grid(grid: G, prop: DomainProperties) -> Self {
         Self {
+            data: DomainRuntime::new(&grid),
+            temp: DomainTemp::new(&grid),
             prop,
-            ..Default::default()
+            emitters: Default::default(),
+            controllers: Default::default(),
+            probes: Default::default(),
+            config: None,
+            transition: None,
+            grid,
         }
     }
 
+    /// Properties validated by [`DomainBuilder::build`](crate::DomainBuilder::build),
+    /// as they were when the domain was built. `None` for domains created
+    /// otherwise.
+    pub fn config(&self) -> Option<&DomainProperties> {
+        self.config.as_ref()
+    }
+
     pub fn pressure(&self, c: &Coords) -> [f32; P_SIZE] {
         self.data.pressure.slice(c).map(|x| *x)
     }
 
     pub fn set_pressure(&mut self, c: &Coords, v: &[f32; P_SIZE]) {
-        for i in 0..P_SIZE {
-            *self.data.pressure.slice_mut(c)[i] = v[i];
+        for (dst, src) in self.data.pressure.slice_mut(c).into_iter().zip(v) {
+            *dst = *src;
         }
     }
 
@@ -47,27 +107,136 @@ impl<const P_SIZE: usize, const X: usize, const Y: usize, const Z: usize> Domain
         *vel[2] = v.2;
     }
 
+    /// Same as [`Self::pressure`], errors instead of panicking on cells
+    /// outside of the grid.
+    pub fn try_pressure(&self, c: &Coords) -> Result<[f32; P_SIZE], DomainError> {
+        self.check_coords(c)?;
+        Ok(self.pressure(c))
+    }
+
+    pub fn try_set_pressure(&mut self, c: &Coords, v: &[f32; P_SIZE]) -> Result<(), DomainError> {
+        self.check_coords(c)?;
+        self.set_pressure(c, v);
+        Ok(())
+    }
+
+    pub fn try_velocity(&self, c: &Coords) -> Result<(f32, f32, f32), DomainError> {
+        self.check_coords(c)?;
+        Ok(self.velocity(c))
+    }
+
+    pub fn try_set_velocity(&mut self, c: &Coords, v: (f32, f32, f32)) -> Result<(), DomainError> {
+        self.check_coords(c)?;
+        self.set_velocity(c, v);
+        Ok(())
+    }
+
+    pub(crate) fn check_coords(&self, c: &Coords) -> Result<(), DomainError> {
+        let size = self.size();
+        if c.0 < size.0 && c.1 < size.1 && c.2 < size.2 {
+            Ok(())
+        } else {
+            Err(DomainError::OutOfBounds { coords: *c, size })
+        }
+    }
+
+    /// Pressure at a world position, interpolated trilinearly between cell
+    /// centres without crossing blocked faces.
+    pub fn sample_pressure(&self, pos: [f32; 3]) -> [f32; P_SIZE] {
+        let pos = self.world_to_cell(pos);
+        let chains = self.data.pressure.chains();
+        let bounds = self.pressure_bounds();
+        std::array::from_fn(|i| sampling::sample(&chains[i], &self.data.blockage, &bounds[i], pos))
+    }
+
+    /// Velocity at a world position, see [`Self::sample_pressure`].
+    pub fn sample_velocity(&self, pos: [f32; 3]) -> (f32, f32, f32) {
+        let pos = self.world_to_cell(pos);
+        let chains = self.data.velocity.chains();
+        let bounds = self.velocity_bounds();
+        let [x, y, z] = std::array::from_fn(|i| {
+            sampling::sample(&chains[i], &self.data.blockage, &bounds[i], pos)
+        });
+        (x, y, z)
+    }
+
+    /// Advances the simulation by one step.
+    ///
+    /// Passes run on the current rayon pool. Every reduction has a fixed
+    /// order, so fields are bit-identical whatever the number of threads.
+    ///
+    /// Non-finite values are handled as set in `validation`, see
+    /// [`Self::simulate_checked`] to learn about them.
     pub fn simulate(&mut self) {
-        // apply modifications from user
+        let _ = self.simulate_checked();
+    }
+
+    /// Same as [`Self::simulate`], returns the first pass that left NaN or
+    /// an infinity behind when `validation` is on. Recovery policies that
+    /// repair fields finish the step before returning the error.
+    pub fn simulate_checked(&mut self) -> Result<(), NonFiniteError> {
+        self.advance_transition();
+        self.sim_controllers();
+        let result = self.step();
+        self.sim_probes();
+        result
+    }
+
+    fn step(&mut self) -> Result<(), NonFiniteError> {
+        if self.prop.validation == Some(Recovery::Rollback) {
+            self.backup();
+        }
+        let mut first = None;
+
+        // apply modifications from user and emitters
+        self.sim_emitters();
         self.data.pressure.swap_buffers();
         self.data.velocity.swap_buffers();
+        self.validate(Pass::Input, &mut first)?;
 
         // simulate next frame
         self.sim_diffusion();
+        self.validate(Pass::Diffusion, &mut first)?;
         self.sim_forces();
+        self.validate(Pass::Forces, &mut first)?;
+        self.sim_projection();
+        self.validate(Pass::Projection, &mut first)?;
         self.sim_advection();
+        self.validate(Pass::Advection, &mut first)?;
+        first.map_or(Ok(()), Err)
+    }
+
+    /// Checks properties before running [`Self::simulate_checked`], so a
+    /// step is never started with values that would panic or silently do
+    /// nothing.
+    pub fn try_simulate(&mut self) -> Result<(), DomainError> {
+        check_properties::<P_SIZE>(&self.prop)?;
+        Ok(self.simulate_checked()?)
+    }
+
+    fn pressure_bounds(&self) -> [Bounds; P_SIZE] {
+        std::array::from_fn(|i| Bounds::pressure(&self.prop.boundaries, self.size(), i))
+    }
+
+    fn velocity_bounds(&self) -> [Bounds; 3] {
+        std::array::from_fn(|i| Bounds::velocity(&self.prop.boundaries, self.size(), i))
     }
 
     fn sim_diffusion(&mut self) {
-        let force = self.prop.pressure_props.diffusion;
-        for _ in 0..self.prop.diffusion_steps {
-            for (src, dst) in self.data.pressure.rw_pairs() {
-                diffusion::diffusion_step(
-                    dst,
-                    src,
-                    &self.data.blockage,
-                    force / self.prop.diffusion_steps as f32,
-                );
+        let steps = self.prop.diffusion_steps;
+        let forces: [f32; P_SIZE] =
+            std::array::from_fn(|i| self.prop.channel(i).diffusion / steps as f32);
+        let bounds = self.pressure_bounds();
+        for _ in 0..steps {
+            let pairs = self
+                .data
+                .pressure
+                .rw_pairs()
+                .into_iter()
+                .zip(&bounds)
+                .zip(forces);
+            for (((src, dst), bounds), force) in pairs {
+                diffusion::diffusion_step(dst, src, &self.data.blockage, bounds, force);
             }
             // swapchain
             self.data.pressure.swap_buffers();
@@ -78,80 +247,229 @@ impl<const P_SIZE: usize, const X: usize, const Y: usize, const Z: usize> Domain
         if let Some(decay) = self.prop.velocity_decay {
             let coefficient = (1.0 - decay).powf(self.prop.step_delta_time);
             for (src, dst) in self.data.velocity.rw_pairs() {
-                forces::decay_velocity(dst, src, coefficient);
+                forces::decay(dst, src, coefficient);
             }
             self.data.velocity.swap_buffers();
         }
+        let channels: [_; P_SIZE] = std::array::from_fn(|i| self.prop.channel(i));
+        if channels.iter().any(|c| c.decay.is_some()) {
+            // untouched producers already hold the consumer values
+            let pairs = self.data.pressure.rw_pairs().into_iter().zip(&channels);
+            for ((src, dst), channel) in pairs {
+                if let Some(decay) = channel.decay {
+                    let coefficient = (1.0 - decay).powf(self.prop.step_delta_time);
+                    forces::decay(dst, src, coefficient);
+                }
+            }
+            self.data.pressure.swap_buffers();
+        }
         if let Some(pressure_acceleration) = self.prop.pressure_acceleration {
-            let force = pressure_acceleration * self.prop.step_delta_time;
-            forces::pressuarize(&mut self.data.velocity, &self.data.pressure, force);
+            // pressure difference between neighbours over the cell size
+            let force = pressure_acceleration * self.prop.step_delta_time / self.prop.cell_size;
+            let bounds = self.pressure_bounds();
+            let [(_, vx), (_, vy), (_, vz)] = self.data.velocity.rw_pairs();
+            forces::pressuarize(
+                [vx, vy, vz],
+                &self.data.pressure,
+                &self.data.blockage,
+                &bounds,
+                &channels.map(|c| c.pressure_weight),
+                force,
+            );
+            self.data.velocity.swap_buffers();
+        }
+        // Missing channels are reported by try_simulate and the builder,
+        // simulate leaves the force out
+        let buoyancy = self.prop.buoyancy.as_ref();
+        if let Some(b) = buoyancy.filter(|b| b.temperature < P_SIZE && b.density < P_SIZE) {
+            let chains = self.data.pressure.chains();
+            let [(_, vx), (_, vy), (_, vz)] = self.data.velocity.rw_pairs();
+            forces::buoyancy(
+                [vx, vy, vz],
+                chains[b.temperature].consumer(),
+                chains[b.density].consumer(),
+                &self.data.blockage,
+                b,
+                self.prop.step_delta_time,
+            );
             self.data.velocity.swap_buffers();
         }
         if let Some(vorticity) = self.prop.vorticity {
+            // Confinement is vorticity * cell size * curl, curl taken in cells
+            // is already cell size times the one in world units
             let force = vorticity * self.prop.step_delta_time;
-            forces::generate_vortexes(&mut self.temp.vorticies, &self.data.velocity);
-            forces::apply_vortex(&mut self.data.velocity, &self.temp.vorticies, force);
+            let bounds = self.velocity_bounds();
+            match self.prop.vorticity_mode {
+                VorticityMode::Legacy => {
+                    let velocity = &self.data.velocity;
+                    forces::generate_vortexes(&mut self.temp.vorticies, velocity, &bounds[0]);
+                    let [(_, vx), (_, vy), (_, vz)] = self.data.velocity.rw_pairs();
+                    forces::apply_vortex([vx, vy, vz], &self.temp.vorticies, &bounds[0], force);
+                }
+                VorticityMode::Curl => {
+                    let (velocity, blockage) = (&self.data.velocity, &self.data.blockage);
+                    let curl = self.temp.curl.get_or_insert_with(|| self.grid.alloc());
+                    forces::generate_curl(curl, velocity, blockage, &bounds);
+                    let [(_, vx), (_, vy), (_, vz)] = self.data.velocity.rw_pairs();
+                    forces::confine_vorticity([vx, vy, vz], curl, blockage, &bounds[0], force);
+                }
+            }
             self.data.velocity.swap_buffers();
         }
     }
 
+    fn sim_projection(&mut self) {
+        let iterations = self.prop.projection_iterations;
+        if iterations == 0 {
+            return;
+        }
+        let bounds = self.velocity_bounds();
+        let grid = &self.grid;
+        let new = || projection::ProjectionTemp::new(grid.alloc());
+        let temp = self.temp.projection.get_or_insert_with(new);
+        let [(_, vx), (_, vy), (_, vz)] = self.data.velocity.rw_pairs();
+        projection::project(
+            [vx, vy, vz],
+            &self.data.blockage,
+            &bounds,
+            temp,
+            iterations,
+            self.prop.projection_tolerance,
+        );
+        self.data.velocity.swap_buffers();
+    }
+
     fn sim_advection(&mut self) {
-        // Change advection scale depending on grid size. Smaller grids means larger
-        // cells, so scale should be smaller. Average dimension size of std_dimension
-        // value (100) equals an advection_scale of 1
-        let avg_dimension = (X + Y + Z) as f32 / 3.0;
-        let std_dimension = 100.0f32;
-        let scale = avg_dimension / std_dimension * self.prop.step_delta_time;
+        // Velocity is in world units, advection moves values by cells
+        let scale = self.prop.step_delta_time / self.prop.cell_size;
         if scale <= f32::EPSILON {
             return;
         }
+        let velocity_bounds = self.velocity_bounds();
+        let pressure_bounds = self.pressure_bounds();
 
-        // cleanup totals
-        for c in iterator::iterate(self.size())
-        {
-            *self.temp.forward_velocity_coefficients_totals.slice_mut(&c) = 0.0;
-            *self.temp.reverse_velocity_coefficients_totals.slice_mut(&c) = 0.0;
-            *self.temp.pressure_coefficients_totals.slice_mut(&c) = 0.0;
-        }
         // Advection order makes significant differences
         // Advecting pressure first leads to self-maintaining waves and ripple
         // artifacts Advecting velocity first naturally dissipates the waves
+        // Both packs move along the velocity of the previous step, so it is
+        // published only once pressure is done
+        let velocity_force = scale * self.prop.velocity_props.advection;
+        match self.prop.velocity_props.scheme {
+            AdvectionScheme::ForwardReverse => {
+                self.generate_coefficients(&velocity_bounds[0], velocity_force, false);
+                let pairs = self.data.velocity.rw_pairs();
+                for ((r, w), bounds) in pairs.into_iter().zip(&velocity_bounds) {
+                    advection::advect(w, r, &self.temp.forward_velocity_coefficients, bounds);
+                }
+            }
+            scheme => {
+                self.trace_cells(&velocity_bounds, velocity_force, scheme);
+                let pairs = self.data.velocity.rw_pairs();
+                for ((r, w), bounds) in pairs.into_iter().zip(&velocity_bounds) {
+                    Self::advect_traced(r, w, &mut self.temp, &self.data.blockage, bounds, scheme);
+                }
+            }
+        }
+
+        // Channels advected at the same rate share coefficients or traces
+        let scheme = self.prop.pressure_props.scheme;
+        let mut prepared = None;
+        for (channel, bounds) in pressure_bounds.iter().enumerate() {
+            let force = scale * self.prop.channel(channel).advection;
+            if prepared != Some(force) {
+                match scheme {
+                    AdvectionScheme::ForwardReverse => {
+                        self.generate_coefficients(&velocity_bounds[0], force, true)
+                    }
+                    scheme => self.trace_cells(&velocity_bounds, force, scheme),
+                }
+                prepared = Some(force);
+            }
+            let (r, w) = self.data.pressure.chains_mut()[channel].rw_pair();
+            match scheme {
+                AdvectionScheme::ForwardReverse => {
+                    advection::advect(w, r, &self.temp.pressure_coefficients, bounds)
+                }
+                scheme => {
+                    Self::advect_traced(r, w, &mut self.temp, &self.data.blockage, bounds, scheme)
+                }
+            }
+        }
+
+        self.data.velocity.swap_buffers();
+        self.data.pressure.swap_buffers();
+    }
+
+    /// Forward and reverse coefficients of the velocity pack, or the
+    /// coefficients of the pressure pack.
+    fn generate_coefficients(&mut self, topology: &Bounds, force: f32, pressure: bool) {
+        // coefficients only depend on shape of the boundary, not on its values
+        let temp = &mut self.temp;
+        let (velocity, blockage) = (&self.data.velocity, &self.data.blockage);
+        if pressure {
+            advection::generate_advection_coefficients(
+                &mut temp.pressure_coefficients,
+                &mut temp.pressure_coefficients_totals,
+                velocity,
+                blockage,
+                topology,
+                force,
+            );
+            return;
+        }
         advection::generate_advection_coefficients(
-            &mut self.temp.forward_velocity_coefficients,
-            &mut self.temp.forward_velocity_coefficients_totals,
-            &self.data.velocity,
-            &self.data.blockage,
-            scale * self.prop.velocity_props.advection,
-        );
-        advection::generate_advection_coefficients(
-            &mut self.temp.reverse_velocity_coefficients,
-            &mut self.temp.reverse_velocity_coefficients_totals,
-            &self.data.velocity,
-            &self.data.blockage,
-            -scale * self.prop.velocity_props.advection,
+            &mut temp.forward_velocity_coefficients,
+            &mut temp.forward_velocity_coefficients_totals,
+            velocity,
+            blockage,
+            topology,
+            force,
         );
         advection::generate_advection_coefficients(
-            &mut self.temp.pressure_coefficients,
-            &mut self.temp.pressure_coefficients_totals,
-            &self.data.velocity,
-            &self.data.blockage,
-            scale * self.prop.pressure_props.advection,
+            &mut temp.reverse_velocity_coefficients,
+            &mut temp.reverse_velocity_coefficients_totals,
+            velocity,
+            blockage,
+            topology,
+            -force,
         );
+    }
 
-        for (r, w) in self.data.velocity.rw_pairs() {
-            advection::forward_advection(w, r, &self.temp.forward_velocity_coefficients);
+    /// Departure points of every cell for the semi-Lagrangian schemes, and
+    /// arrival points when MacCormack needs them.
+    fn trace_cells(&mut self, bounds: &[Bounds; 3], force: f32, scheme: AdvectionScheme) {
+        let velocity = self.data.velocity.chains().each_ref();
+        let blockage = &self.data.blockage;
+        let grid = &self.grid;
+        let traces = self.temp.traces.get_or_insert_with(|| Traces::new(grid));
+        semi_lagrangian::trace_cells(&mut traces.departure, velocity, blockage, bounds, -force);
+        if scheme == AdvectionScheme::MacCormack {
+            semi_lagrangian::trace_cells(&mut traces.arrival, velocity, blockage, bounds, force);
         }
-        for (r, w) in self.data.velocity.rw_pairs() {
-            advection::reverse_advection(w, r, &self.temp.forward_velocity_coefficients);
-        }
-        self.data.velocity.swap_buffers();
+    }
 
-        for (r, w) in self.data.pressure.rw_pairs() {
-            advection::forward_advection(w, r, &self.temp.pressure_coefficients);
-        }
-        for (r, w) in self.data.pressure.rw_pairs() {
-            advection::reverse_advection(w, r, &self.temp.pressure_coefficients);
+    fn advect_traced(
+        r: &G::Array<f32>,
+        w: &mut G::Array<f32>,
+        temp: &mut DomainTemp<G>,
+        blockage: &G::Array<FlowFlags>,
+        bounds: &Bounds,
+        scheme: AdvectionScheme,
+    ) {
+        let traces = temp.traces.as_mut().expect("cells are traced first");
+        if scheme != AdvectionScheme::MacCormack {
+            semi_lagrangian::advect(w, r, &traces.departure, blockage, bounds);
+            return;
         }
-        self.data.pressure.swap_buffers();
+        semi_lagrangian::advect(&mut traces.advected, r, &traces.departure, blockage, bounds);
+        semi_lagrangian::correct(
+            w,
+            r,
+            &traces.advected,
+            &traces.departure,
+            &traces.arrival,
+            blockage,
+            bounds,
+        );
     }
 }
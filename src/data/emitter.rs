@@ -0,0 +1,181 @@
+use crate::{
+    iterator,
+    math::{grid::Grid, Slice3DMut},
+    Coords, DomainError, GridDomain, Sized3D,
+};
+
+/// Region of the grid, in cell units.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Shape {
+    Point(Coords),
+    /// Cells in `from..to`, `to` exclusive on every axis.
+    Box {
+        from: Coords,
+        to: Coords,
+    },
+    /// Cells whose centres lie within `radius` of `center`.
+    Sphere {
+        center: [f32; 3],
+        radius: f32,
+    },
+}
+
+impl Shape {
+    /// Cells of the shape that lie inside a grid of `size`.
+    pub fn cells(&self, size: Coords) -> impl Iterator<Item = Coords> + '_ {
+        let (from, to) = match self {
+            Shape::Point(c) => (*c, Coords(c.0 + 1, c.1 + 1, c.2 + 1)),
+            Shape::Box { from, to } => (*from, *to),
+            Shape::Sphere { center, radius } => {
+                let low = |v: f32| (v - radius).ceil().max(0.0) as usize;
+                let high = |v: f32| ((v + radius).floor() + 1.0).max(0.0) as usize;
+                (
+                    Coords(low(center[0]), low(center[1]), low(center[2])),
+                    Coords(high(center[0]), high(center[1]), high(center[2])),
+                )
+            }
+        };
+        let to = Coords(to.0.min(size.0), to.1.min(size.1), to.2.min(size.2));
+        iterator::iterate_range(from, to).filter(move |c| match self {
+            Shape::Sphere { center, radius } => {
+                let dx = c.0 as f32 - center[0];
+                let dy = c.1 as f32 - center[1];
+                let dz = c.2 as f32 - center[2];
+                dx * dx + dy * dy + dz * dz <= radius * radius
+            }
+            _ => true,
+        })
+    }
+}
+
+/// Adds pressure and pushes velocity in a region every step. Rates are per
+/// unit of time and scaled by `step_delta_time`.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Emitter {
+    pub shape: Shape,
+    /// Pressure channel and amount added to every covered cell.
+    pub pressure: Option<(usize, f32)>,
+    /// Acceleration applied to every covered cell.
+    pub velocity: Option<[f32; 3]>,
+}
+
+/// Drains pressure in a region every step. Rate is the fraction removed per
+/// unit of time, scaled by `step_delta_time` and capped at the whole cell.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Sink {
+    pub shape: Shape,
+    pub channel: usize,
+    pub rate: f32,
+}
+
+/// Identifies an emitter or a sink attached to a domain.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct EmitterHandle(u64);
+
+#[derive(Clone, Debug)]
+enum Source {
+    Emitter(Emitter),
+    Sink(Sink),
+}
+
+/// Emitters and sinks, applied in order of addition.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Emitters {
+    next: u64,
+    sources: Vec<(EmitterHandle, Source)>,
+}
+
+impl Emitters {
+    fn add(&mut self, source: Source) -> EmitterHandle {
+        let handle = EmitterHandle(self.next);
+        self.next += 1;
+        self.sources.push((handle, source));
+        handle
+    }
+
+    pub(crate) fn emitter_mut(&mut self, handle: EmitterHandle) -> Option<&mut Emitter> {
+        self.sources.iter_mut().find_map(|(h, s)| match s {
+            Source::Emitter(e) if *h == handle => Some(e),
+            _ => None,
+        })
+    }
+
+    pub(crate) fn sink_mut(&mut self, handle: EmitterHandle) -> Option<&mut Sink> {
+        self.sources.iter_mut().find_map(|(h, s)| match s {
+            Source::Sink(sink) if *h == handle => Some(sink),
+            _ => None,
+        })
+    }
+}
+
+fn check_channel<const P_SIZE: usize>(property: &str, channel: usize) -> Result<(), DomainError> {
+    if channel >= P_SIZE {
+        return Err(DomainError::InvalidProperty {
+            property: property.into(),
+            reason: "names a missing pressure channel",
+        });
+    }
+    Ok(())
+}
+
+impl<const P_SIZE: usize, G: Grid> GridDomain<P_SIZE, G> {
+    /// Errors when the emitter names a missing pressure channel.
+    pub fn add_emitter(&mut self, emitter: Emitter) -> Result<EmitterHandle, DomainError> {
+        if let Some((channel, _)) = emitter.pressure {
+            check_channel::<P_SIZE>("pressure", channel)?;
+        }
+        Ok(self.emitters.add(Source::Emitter(emitter)))
+    }
+
+    /// Errors when the sink names a missing pressure channel.
+    pub fn add_sink(&mut self, sink: Sink) -> Result<EmitterHandle, DomainError> {
+        check_channel::<P_SIZE>("channel", sink.channel)?;
+        Ok(self.emitters.add(Source::Sink(sink)))
+    }
+
+    /// Detaches emitter or sink, returns false if it was already removed.
+    pub fn remove_emitter(&mut self, handle: EmitterHandle) -> bool {
+        let sources = &mut self.emitters.sources;
+        let len = sources.len();
+        sources.retain(|(h, _)| *h != handle);
+        sources.len() != len
+    }
+
+    /// Applies emitters and sinks on top of the values set by the user. Solid
+    /// cells are left alone.
+    pub(crate) fn sim_emitters(&mut self) {
+        let dt = self.prop.step_delta_time;
+        let size = self.size();
+        for (_, source) in &self.emitters.sources {
+            match source {
+                Source::Emitter(e) => {
+                    for c in e.shape.cells(size) {
+                        if self.is_solid(&c) {
+                            continue;
+                        }
+                        if let Some((channel, rate)) = e.pressure {
+                            *self.data.pressure.slice_mut(&c)[channel] += rate * dt;
+                        }
+                        if let Some(acceleration) = e.velocity {
+                            let vel = self.data.velocity.slice_mut(&c);
+                            for (v, a) in vel.into_iter().zip(acceleration) {
+                                *v += a * dt;
+                            }
+                        }
+                    }
+                }
+                Source::Sink(s) => {
+                    let keep = 1.0 - (s.rate * dt).clamp(0.0, 1.0);
+                    for c in s.shape.cells(size) {
+                        if !self.is_solid(&c) {
+                            *self.data.pressure.slice_mut(&c)[s.channel] *= keep;
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
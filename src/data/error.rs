@@ -0,0 +1,48 @@
+use std::fmt;
+
+use crate::{Coords, NonFiniteError};
+
+/// Error of the fallible domain API.
+#[derive(Clone, Debug, PartialEq)]
+pub enum DomainError {
+    /// Cell lies outside of the grid.
+    OutOfBounds {
+        coords: Coords,
+        size: Coords,
+    },
+    /// A property cannot be simulated, `property` names the field.
+    InvalidProperty {
+        property: String,
+        reason: &'static str,
+    },
+    NonFinite(NonFiniteError),
+}
+
+impl fmt::Display for DomainError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::OutOfBounds { coords, size } => {
+                write!(f, "cell {:?} is outside of grid {:?}", coords, size)
+            }
+            Self::InvalidProperty { property, reason } => {
+                write!(f, "invalid `{}`: {}", property, reason)
+            }
+            Self::NonFinite(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for DomainError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::NonFinite(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<NonFiniteError> for DomainError {
+    fn from(e: NonFiniteError) -> Self {
+        Self::NonFinite(e)
+    }
+}
@@ -1,5 +1,8 @@
+use crate::math::{coords, CoordsDiff};
+
 bitflags! {
     #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
     pub struct FlowFlags: u8 {
         const X_FORW = 0b00000001;
         const Y_FORW = 0b00000010;
@@ -15,3 +18,22 @@ impl Default for FlowFlags {
         FlowFlags::empty()
     }
 }
+
+/// Face flags paired with the offset to the neighbouring cell behind that face.
+pub const FACES: [(FlowFlags, CoordsDiff); 6] = [
+    (FlowFlags::X_FORW, coords::X_FORW),
+    (FlowFlags::Y_FORW, coords::Y_FORW),
+    (FlowFlags::Z_FORW, coords::Z_FORW),
+    (FlowFlags::X_BACK, coords::X_BACK),
+    (FlowFlags::Y_BACK, coords::Y_BACK),
+    (FlowFlags::Z_BACK, coords::Z_BACK),
+];
+
+impl FlowFlags {
+    /// Same faces as seen from the neighbouring cells.
+    pub fn opposite(self) -> Self {
+        let forw = self & (Self::X_FORW | Self::Y_FORW | Self::Z_FORW);
+        let back = self & (Self::X_BACK | Self::Y_BACK | Self::Z_BACK);
+        Self::from_bits_retain((forw.bits() << 3) | (back.bits() >> 3))
+    }
+}
@@ -1,4 +1,21 @@
+pub mod blockage;
+pub mod builder;
+pub mod control;
+pub mod diagnostics;
 pub mod domain;
+pub mod emitter;
+pub mod error;
 pub mod flow;
+pub mod particles;
+pub mod probes;
 pub mod properties;
+pub mod reload;
 pub mod runtime;
+#[cfg(feature = "serde")]
+pub mod scene;
+#[cfg(feature = "serde")]
+pub mod snapshot;
+pub mod timestep;
+pub mod transform;
+pub mod validation;
+pub mod vtk;
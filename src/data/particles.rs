@@ -0,0 +1,247 @@
+use rayon::prelude::*;
+
+use crate::{
+    algorithm::{boundary::Bounds, trace},
+    data::emitter::Shape,
+    math::grid::Grid,
+    GridDomain, Sized3D,
+};
+
+/// Time integration of particle paths through the velocity field.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Integrator {
+    /// Forward Euler, one velocity sample per step.
+    Rk1,
+    /// Midpoint method, two samples per step.
+    #[default]
+    Rk2,
+    /// Classic fourth order Runge-Kutta, four samples per step.
+    Rk4,
+}
+
+/// What a particle does when its path crosses a blocked face or a wall.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Collision {
+    /// Rest of the path is mirrored about the face.
+    #[default]
+    Bounce,
+    /// Particle rests against the face.
+    Stop,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Particle<A> {
+    /// World position.
+    pub position: [f32; 3],
+    /// Time since emission.
+    pub age: f32,
+    pub attributes: A,
+}
+
+/// Emits `rate` particles per unit of time at random positions within the
+/// non-solid cells of a shape.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParticleSource<A> {
+    pub shape: Shape,
+    pub rate: f32,
+    /// Given to every emitted particle.
+    pub attributes: A,
+}
+
+/// Tracer particles carried through the velocity field of a domain.
+///
+/// Particles move as the pressure does, velocity scaled by
+/// `pressure_props.advection`. Channels with their own entry in
+/// `pressure_channels` do not change that rate. Particles are removed when
+/// leaving through an outflow or inflow face, when found more than a cell
+/// outside of the domain or once older than `lifetime`.
+///
+/// Particles follow the published fields, so they are stepped right after
+/// the domain, over the time the domain has advanced:
+///
+/// ```
+/// use fluid_simulation::{Domain, Particles};
+///
+/// let mut domain: Domain<1, 8, 8, 8> = Default::default();
+/// let mut particles = Particles::new();
+/// particles.spawn([4.0, 4.0, 4.0], ());
+/// for _ in 0..10 {
+///     domain.simulate();
+///     particles.step(&domain, domain.prop.step_delta_time);
+/// }
+/// assert_eq!(particles.len(), 1);
+/// ```
+#[derive(Clone, Debug)]
+pub struct Particles<A = ()> {
+    pub integrator: Integrator,
+    pub collision: Collision,
+    pub lifetime: Option<f32>,
+    particles: Vec<Particle<A>>,
+    // each source keeps the fraction of a particle not yet emitted
+    sources: Vec<(ParticleSource<A>, f32)>,
+    seed: u32,
+}
+
+impl<A> Default for Particles<A> {
+    fn default() -> Self {
+        Self {
+            integrator: Default::default(),
+            collision: Default::default(),
+            lifetime: None,
+            particles: Vec::new(),
+            sources: Vec::new(),
+            seed: 0x9e37_79b9,
+        }
+    }
+}
+
+impl<A: Clone + Send> Particles<A> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn spawn(&mut self, position: [f32; 3], attributes: A) {
+        self.particles.push(Particle {
+            position,
+            age: 0.0,
+            attributes,
+        });
+    }
+
+    pub fn add_source(&mut self, source: ParticleSource<A>) {
+        self.sources.push((source, 0.0));
+    }
+
+    pub fn clear_sources(&mut self) {
+        self.sources.clear();
+    }
+
+    pub fn particles(&self) -> &[Particle<A>] {
+        &self.particles
+    }
+
+    pub fn particles_mut(&mut self) -> &mut Vec<Particle<A>> {
+        &mut self.particles
+    }
+
+    pub fn len(&self) -> usize {
+        self.particles.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.particles.is_empty()
+    }
+
+    /// Moves particles through the published velocity of the domain over
+    /// `dt`, then removes expired ones and emits new ones. Call after every
+    /// [`GridDomain::simulate`] with its `step_delta_time`, or after
+    /// [`GridDomain::simulate_for`] with the whole interval. The latter
+    /// integrates over the last velocity only.
+    pub fn step<const P_SIZE: usize, G: Grid>(&mut self, domain: &GridDomain<P_SIZE, G>, dt: f32) {
+        // Channel does not matter, only the kind of every edge is used
+        let bounds = Bounds::pressure(&domain.prop.boundaries, domain.size(), 0);
+        let (integrator, collision, lifetime) = (self.integrator, self.collision, self.lifetime);
+
+        // Every particle moves on its own, order of the survivors is kept
+        let keep: Vec<bool> = self
+            .particles
+            .par_iter_mut()
+            .map(|p| {
+                p.age += dt;
+                if lifetime.is_some_and(|l| p.age > l) {
+                    return false;
+                }
+                let from = domain.world_to_cell(p.position);
+                if !bounds.contains(from) {
+                    return false;
+                }
+                let to = integrate(domain, integrator, p.position, dt);
+                let bounce = collision == Collision::Bounce;
+                // Far targets would walk or bounce cell by cell for ages
+                let mut to = domain.world_to_cell(to);
+                bounds.limit(from, &mut to);
+                match trace::travel(&domain.data.blockage, &bounds, bounce, from, to) {
+                    Some(mut pos) => {
+                        bounds.enclose(&mut pos);
+                        p.position = domain.cell_to_world(pos);
+                        true
+                    }
+                    None => false,
+                }
+            })
+            .collect();
+        let mut keep = keep.into_iter();
+        self.particles.retain(|_| keep.next().unwrap_or(false));
+
+        self.emit(domain, dt);
+    }
+
+    fn emit<const P_SIZE: usize, G: Grid>(&mut self, domain: &GridDomain<P_SIZE, G>, dt: f32) {
+        let size = domain.size();
+        for (source, carry) in &mut self.sources {
+            *carry += source.rate * dt;
+            let count = carry.floor();
+            *carry -= count;
+            let cells: Vec<_> = source
+                .shape
+                .cells(size)
+                .filter(|c| !domain.is_solid(c))
+                .collect();
+            if cells.is_empty() {
+                continue;
+            }
+            for _ in 0..count as usize {
+                let c = cells[next_random(&mut self.seed) as usize % cells.len()];
+                let mut offset = || next_random(&mut self.seed) as f32 / u32::MAX as f32 - 0.5;
+                let pos = [
+                    c.0 as f32 + offset(),
+                    c.1 as f32 + offset(),
+                    c.2 as f32 + offset(),
+                ];
+                self.particles.push(Particle {
+                    position: domain.cell_to_world(pos),
+                    age: 0.0,
+                    attributes: source.attributes.clone(),
+                });
+            }
+        }
+    }
+}
+
+// xorshift32, enough to scatter particles and keeps runs repeatable
+fn next_random(state: &mut u32) -> u32 {
+    *state ^= *state << 13;
+    *state ^= *state >> 17;
+    *state ^= *state << 5;
+    *state
+}
+
+/// End of the step starting at world position `pos`.
+fn integrate<const P_SIZE: usize, G: Grid>(
+    domain: &GridDomain<P_SIZE, G>,
+    integrator: Integrator,
+    pos: [f32; 3],
+    dt: f32,
+) -> [f32; 3] {
+    let speed = domain.prop.pressure_props.advection;
+    let velocity = |p: [f32; 3]| {
+        let (x, y, z) = domain.sample_velocity(p);
+        [x * speed, y * speed, z * speed]
+    };
+    let offset = |k: [f32; 3], t: f32| std::array::from_fn(|i| pos[i] + k[i] * t);
+    match integrator {
+        Integrator::Rk1 => offset(velocity(pos), dt),
+        Integrator::Rk2 => {
+            let k1 = velocity(pos);
+            offset(velocity(offset(k1, dt / 2.0)), dt)
+        }
+        Integrator::Rk4 => {
+            let k1 = velocity(pos);
+            let k2 = velocity(offset(k1, dt / 2.0));
+            let k3 = velocity(offset(k2, dt / 2.0));
+            let k4 = velocity(offset(k3, dt));
+            let k = std::array::from_fn(|i| (k1[i] + 2.0 * k2[i] + 2.0 * k3[i] + k4[i]) / 6.0);
+            offset(k, dt)
+        }
+    }
+}
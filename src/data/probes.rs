@@ -0,0 +1,259 @@
+use std::{
+    collections::VecDeque,
+    io::{self, BufWriter, Write},
+};
+
+use crate::{
+    algorithm::boundary::{Bounds, Edge},
+    data::{builder::check_shape, emitter::Shape, flow::FACES},
+    math::grid::Grid,
+    Coords, DomainError, FlowFlags, GridDomain, Sized3D,
+};
+
+/// What a probe records after every step.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Probe {
+    /// Fields of a single cell.
+    Point(Coords),
+    /// Mean fields over the open cells of a region.
+    Average(Shape),
+    /// Flow through a face of every cell of a region, positive when leaving
+    /// the cell. `face` must be exactly one flag.
+    Flux { shape: Shape, face: FlowFlags },
+}
+
+/// Single record of a probe.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Reading<const P_SIZE: usize> {
+    /// Simulated time at the end of the step.
+    pub time: f32,
+    /// Pressure of a point or a region, for flux probes the amount of each
+    /// channel carried across per unit of time.
+    pub pressure: [f32; P_SIZE],
+    /// Velocity of a point or a region, zero for flux probes.
+    pub velocity: [f32; 3],
+    /// Volume crossing per unit of time in world units, zero for other
+    /// probes.
+    pub flow: f32,
+}
+
+/// Latest readings of a probe, oldest are dropped once `capacity` is reached.
+#[derive(Clone, Debug)]
+pub struct ProbeSeries<const P_SIZE: usize> {
+    probe: Probe,
+    capacity: usize,
+    readings: VecDeque<Reading<P_SIZE>>,
+}
+
+impl<const P_SIZE: usize> ProbeSeries<P_SIZE> {
+    pub fn probe(&self) -> &Probe {
+        &self.probe
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Readings from the oldest to the latest.
+    pub fn readings(&self) -> impl ExactSizeIterator<Item = &Reading<P_SIZE>> {
+        self.readings.iter()
+    }
+
+    pub fn latest(&self) -> Option<&Reading<P_SIZE>> {
+        self.readings.back()
+    }
+
+    pub fn len(&self) -> usize {
+        self.readings.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.readings.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.readings.clear();
+    }
+
+    /// Writes readings as CSV with a header row: `time`, `pressure_<channel>`,
+    /// `velocity_x/y/z` and `flow` columns.
+    pub fn write_csv<W: Write>(&self, w: W) -> io::Result<()> {
+        let mut w = BufWriter::new(w);
+        write!(w, "time")?;
+        for i in 0..P_SIZE {
+            write!(w, ",pressure_{}", i)?;
+        }
+        writeln!(w, ",velocity_x,velocity_y,velocity_z,flow")?;
+        for r in &self.readings {
+            write!(w, "{}", r.time)?;
+            for v in r.pressure.iter().chain(&r.velocity) {
+                write!(w, ",{}", v)?;
+            }
+            writeln!(w, ",{}", r.flow)?;
+        }
+        w.flush()
+    }
+
+    fn push(&mut self, reading: Reading<P_SIZE>) {
+        if self.readings.len() == self.capacity {
+            self.readings.pop_front();
+        }
+        self.readings.push_back(reading);
+    }
+}
+
+/// Named probes in order of addition, along with the simulated time.
+#[derive(Clone, Debug)]
+pub(crate) struct Probes<const P_SIZE: usize> {
+    time: f32,
+    series: Vec<(String, ProbeSeries<P_SIZE>)>,
+}
+
+impl<const P_SIZE: usize> Default for Probes<P_SIZE> {
+    fn default() -> Self {
+        Self {
+            time: 0.0,
+            series: Vec::new(),
+        }
+    }
+}
+
+impl<const P_SIZE: usize, G: Grid> GridDomain<P_SIZE, G> {
+    /// Registers a probe keeping its latest `capacity` readings. A probe of
+    /// the same name is replaced along with its readings. Errors when the
+    /// probe reaches outside of the domain or a flux names no single face.
+    pub fn add_probe(
+        &mut self,
+        name: &str,
+        probe: Probe,
+        capacity: usize,
+    ) -> Result<(), DomainError> {
+        match &probe {
+            Probe::Point(c) => self.check_coords(c)?,
+            Probe::Average(shape) => check_shape(shape, self.size())?,
+            Probe::Flux { shape, face } => {
+                if !FACES.iter().any(|(f, _)| f == face) {
+                    return Err(DomainError::InvalidProperty {
+                        property: "face".into(),
+                        reason: "must be exactly one face",
+                    });
+                }
+                check_shape(shape, self.size())?
+            }
+        }
+        let series = ProbeSeries {
+            probe,
+            capacity: capacity.max(1),
+            readings: VecDeque::with_capacity(capacity.max(1)),
+        };
+        let list = &mut self.probes.series;
+        match list.iter_mut().find(|(n, _)| n == name) {
+            Some((_, s)) => *s = series,
+            None => list.push((name.to_string(), series)),
+        }
+        Ok(())
+    }
+
+    /// Detaches probe, returns false if there is none of that name.
+    pub fn remove_probe(&mut self, name: &str) -> bool {
+        let list = &mut self.probes.series;
+        let len = list.len();
+        list.retain(|(n, _)| n != name);
+        list.len() != len
+    }
+
+    pub fn probe(&self, name: &str) -> Option<&ProbeSeries<P_SIZE>> {
+        let mut list = self.probes.series.iter();
+        list.find(|(n, _)| n == name).map(|(_, s)| s)
+    }
+
+    /// Every probe with its name, in order of addition.
+    pub fn probes(&self) -> impl Iterator<Item = (&str, &ProbeSeries<P_SIZE>)> {
+        self.probes.series.iter().map(|(n, s)| (n.as_str(), s))
+    }
+
+    /// Reads every probe off the published fields, called after each step.
+    pub(crate) fn sim_probes(&mut self) {
+        self.probes.time += self.prop.step_delta_time;
+        if self.probes.series.is_empty() {
+            return;
+        }
+        let mut series = std::mem::take(&mut self.probes.series);
+        for (_, s) in &mut series {
+            let reading = self.read(&s.probe);
+            s.push(reading);
+        }
+        self.probes.series = series;
+    }
+
+    fn read(&self, probe: &Probe) -> Reading<P_SIZE> {
+        let mut reading = Reading {
+            time: self.probes.time,
+            pressure: [0.0; P_SIZE],
+            velocity: [0.0; 3],
+            flow: 0.0,
+        };
+        let size = self.size();
+        match probe {
+            Probe::Point(c) => {
+                let (x, y, z) = self.velocity(c);
+                reading.pressure = self.pressure(c);
+                reading.velocity = [x, y, z];
+            }
+            Probe::Average(shape) => {
+                let mut count = 0;
+                for c in shape.cells(size).filter(|c| !self.is_solid(c)) {
+                    let (x, y, z) = self.velocity(&c);
+                    let values = reading.velocity.iter_mut().zip([x, y, z]);
+                    let pressure = reading.pressure.iter_mut().zip(self.pressure(&c));
+                    for (sum, v) in values.chain(pressure) {
+                        *sum += v;
+                    }
+                    count += 1;
+                }
+                let n = count.max(1) as f32;
+                for v in reading.velocity.iter_mut().chain(&mut reading.pressure) {
+                    *v /= n;
+                }
+            }
+            Probe::Flux { shape, face } => {
+                let index = FACES.iter().position(|(f, _)| f == face).unwrap();
+                let axis = index % 3;
+                let sign = if index < 3 { 1.0 } else { -1.0 };
+                let area = self.prop.cell_size * self.prop.cell_size;
+                let velocity = Bounds::velocity(&self.prop.boundaries, size, axis);
+                let pressure: [Bounds; P_SIZE] =
+                    std::array::from_fn(|i| Bounds::pressure(&self.prop.boundaries, size, i));
+                let fields = |c: &Coords| {
+                    let (x, y, z) = self.velocity(c);
+                    ([x, y, z][axis], self.pressure(c))
+                };
+                for c in shape.cells(size) {
+                    if self.blocked_faces(&c).contains(*face) {
+                        continue;
+                    }
+                    let (v, p) = fields(&c);
+                    // values behind the face as in the divergence of the projection
+                    let (vn, pn) = match velocity.neighbour(&c, index) {
+                        Ok(n) => fields(&n),
+                        Err(Edge::Fixed(ext)) => {
+                            let pn =
+                                std::array::from_fn(|i| match pressure[i].neighbour(&c, index) {
+                                    Err(Edge::Fixed(e)) => e,
+                                    _ => p[i],
+                                });
+                            (ext, pn)
+                        }
+                        Err(_) => continue,
+                    };
+                    let flow = sign * 0.5 * (v + vn) * area;
+                    reading.flow += flow;
+                    for ((sum, p), pn) in reading.pressure.iter_mut().zip(p).zip(pn) {
+                        *sum += flow * 0.5 * (p + pn);
+                    }
+                }
+            }
+        }
+        reading
+    }
+}
@@ -1,17 +1,277 @@
+use crate::{DomainError, FlowFlags};
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
 pub struct DomainProperties {
     pub velocity_props: PackProperties,
     pub pressure_props: PackProperties,
+    /// Per pressure channel rates, channels past the end use `pressure_props`
+    /// and push the flow with full weight.
+    pub pressure_channels: Vec<ChannelProperties>,
+
+    /// Edge length of a cell in world units.
+    pub cell_size: f32,
+    /// World position of the centre of the first cell.
+    pub origin: [f32; 3],
 
     pub diffusion_steps: usize,
     pub step_delta_time: f32,
+    /// Cells the fastest pack may travel in a single substep of
+    /// `simulate_for`.
+    pub target_cfl: f32,
+    /// Upper limit on substeps of a single `simulate_for`, the CFL target is
+    /// exceeded rather than going over it.
+    pub max_substeps: usize,
     pub velocity_decay: Option<f32>,
     pub pressure_acceleration: Option<f32>,
     pub vorticity: Option<f32>,
+    pub vorticity_mode: VorticityMode,
+    pub buoyancy: Option<Buoyancy>,
+    /// Conjugate gradient iterations of the pressure projection run between
+    /// forces and advection, zero disables it.
+    pub projection_iterations: usize,
+    /// Projection stops early once root-mean-square divergence falls to this.
+    pub projection_tolerance: f32,
+    pub boundaries: Boundaries,
+    /// Checks the output of every pass for NaN and infinities and recovers
+    /// as given, `None` skips the checks.
+    pub validation: Option<Recovery>,
 }
 
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PackProperties {
     pub advection: f32,
     pub diffusion: f32,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub scheme: AdvectionScheme,
+}
+
+/// Rates of a single pressure channel.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ChannelProperties {
+    pub advection: f32,
+    pub diffusion: f32,
+    /// Weight of the channel in the pressure force, zero leaves it a passive
+    /// dye that rides the flow without pushing it.
+    pub pressure_weight: f32,
+    /// Fraction of the channel lost per unit of time.
+    pub decay: Option<f32>,
+}
+
+/// Hot gas rising and dense smoke sinking, read from two pressure channels.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Buoyancy {
+    /// Pressure channel holding temperature.
+    pub temperature: usize,
+    /// Pressure channel holding density.
+    pub density: usize,
+    /// Temperature at which gas neither rises nor sinks.
+    pub ambient_temperature: f32,
+    /// Weight of density, pulls along gravity.
+    pub alpha: f32,
+    /// Weight of temperature above ambient, pushes against gravity.
+    pub beta: f32,
+    /// Acceleration of gravity in world units.
+    pub gravity: [f32; 3],
+}
+
+/// How vorticity confinement measures and pushes swirls.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum VorticityMode {
+    /// Scalar swirl estimate pushed along its own gradient, kept so tuned
+    /// scenes keep their look.
+    #[default]
+    Legacy,
+    /// Full curl vector per cell and the `N x curl` confinement force,
+    /// respecting blocked faces.
+    Curl,
+}
+
+/// What `simulate` does once a pass leaves non-finite values behind.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Recovery {
+    /// Skips the rest of the step, fields keep the values.
+    Stop,
+    /// Sets NaN to zero and clamps the other values of the offending cells
+    /// into `-limit..=limit`, then goes on.
+    Clamp(f32),
+    /// Zeroes every field of the offending cells, then goes on.
+    Zero,
+    /// Restores fields published before the step and skips the rest of it,
+    /// along with changes made since.
+    Rollback,
+}
+
+/// How a pack is moved along the velocity.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AdvectionScheme {
+    /// Mass conserving forward and reverse transfer between neighbours.
+    /// Movement is limited to 1.5 cells per step, which caps velocity at
+    /// large timesteps.
+    #[default]
+    ForwardReverse,
+    /// Values are sampled at the point traced back along the velocity. Any
+    /// speed is stable, mass is not conserved and values blur over time.
+    SemiLagrangian,
+    /// Semi-Lagrangian with MacCormack error correction, sharper at the cost
+    /// of two more samples per cell.
+    MacCormack,
+}
+
+/// What happens to values at a face of the domain box.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Boundary {
+    /// Nothing crosses the face.
+    Wall,
+    /// Values leave into an empty exterior.
+    Outflow,
+    /// Face wraps around to the opposite face. Must be set on both faces of an
+    /// axis, a lone periodic face acts as a wall.
+    Periodic,
+    /// Exterior holds fixed per-channel pressure (missing channels are zero)
+    /// and velocity.
+    Inflow {
+        pressure: Vec<f32>,
+        velocity: [f32; 3],
+    },
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Boundaries {
+    pub x_forw: Boundary,
+    pub y_forw: Boundary,
+    pub z_forw: Boundary,
+    pub x_back: Boundary,
+    pub y_back: Boundary,
+    pub z_back: Boundary,
+}
+
+impl Boundaries {
+    pub fn all(b: Boundary) -> Self {
+        Self {
+            x_forw: b.clone(),
+            y_forw: b.clone(),
+            z_forw: b.clone(),
+            x_back: b.clone(),
+            y_back: b.clone(),
+            z_back: b,
+        }
+    }
+
+    /// Boundary of a single face, `face` must be exactly one flag.
+    pub fn face(&self, face: FlowFlags) -> &Boundary {
+        match face {
+            FlowFlags::X_FORW => &self.x_forw,
+            FlowFlags::Y_FORW => &self.y_forw,
+            FlowFlags::Z_FORW => &self.z_forw,
+            FlowFlags::X_BACK => &self.x_back,
+            FlowFlags::Y_BACK => &self.y_back,
+            FlowFlags::Z_BACK => &self.z_back,
+            _ => panic!("expected single face, got {:?}", face),
+        }
+    }
+}
+
+impl Default for Boundaries {
+    fn default() -> Self {
+        Self::all(Boundary::Wall)
+    }
+}
+
+impl DomainProperties {
+    /// Properties of a pressure channel, see `pressure_channels`.
+    pub fn channel(&self, channel: usize) -> ChannelProperties {
+        self.pressure_channels
+            .get(channel)
+            .cloned()
+            .unwrap_or(ChannelProperties {
+                advection: self.pressure_props.advection,
+                diffusion: self.pressure_props.diffusion,
+                pressure_weight: 1.0,
+                decay: None,
+            })
+    }
+
+    /// Checks for values the simulation cannot run with: negative or NaN
+    /// advection and diffusion rates, decay above 1 or NaN, diffusion left
+    /// without steps, a non-finite timestep, a CFL target that is not
+    /// positive, no substeps, a negative or NaN projection tolerance and a
+    /// negative or non-finite clamp limit.
+    pub fn validate(&self) -> Result<(), DomainError> {
+        let invalid =
+            |property: String, reason| Err(DomainError::InvalidProperty { property, reason });
+        let packs = [
+            ("velocity_props", &self.velocity_props),
+            ("pressure_props", &self.pressure_props),
+        ];
+        let rates = packs
+            .into_iter()
+            .map(|(name, p)| (name.to_string(), p.advection, p.diffusion))
+            .chain(self.pressure_channels.iter().enumerate().map(|(i, c)| {
+                (
+                    format!("pressure_channels[{}]", i),
+                    c.advection,
+                    c.diffusion,
+                )
+            }));
+        let mut diffuses = false;
+        for (name, advection, diffusion) in rates {
+            if advection.is_nan() || advection < 0.0 {
+                return invalid(format!("{}.advection", name), "must not be negative or NaN");
+            }
+            if diffusion.is_nan() || diffusion < 0.0 {
+                return invalid(format!("{}.diffusion", name), "must not be negative or NaN");
+            }
+            diffuses |= diffusion > 0.0;
+        }
+        if diffuses && self.diffusion_steps == 0 {
+            return invalid("diffusion_steps".into(), "must be positive to diffuse");
+        }
+        let decays = std::iter::once(("velocity_decay".to_string(), self.velocity_decay)).chain(
+            self.pressure_channels
+                .iter()
+                .enumerate()
+                .map(|(i, c)| (format!("pressure_channels[{}].decay", i), c.decay)),
+        );
+        for (name, decay) in decays {
+            if decay.is_some_and(|d| d.is_nan() || d > 1.0) {
+                return invalid(name, "must not be above 1 or NaN");
+            }
+        }
+        if !(self.step_delta_time.is_finite() && self.step_delta_time >= 0.0) {
+            return invalid("step_delta_time".into(), "must be finite and not negative");
+        }
+        if !(self.cell_size.is_finite() && self.cell_size > 0.0) {
+            return invalid("cell_size".into(), "must be finite and positive");
+        }
+        if self.target_cfl.is_nan() || self.target_cfl <= 0.0 {
+            return invalid("target_cfl".into(), "must be positive");
+        }
+        if self.max_substeps == 0 {
+            return invalid("max_substeps".into(), "must be positive");
+        }
+        if self.projection_tolerance.is_nan() || self.projection_tolerance < 0.0 {
+            return invalid("projection_tolerance".into(), "must not be negative or NaN");
+        }
+        if let Some(Recovery::Clamp(limit)) = self.validation {
+            if !(limit.is_finite() && limit >= 0.0) {
+                return invalid(
+                    "validation".into(),
+                    "clamp limit must be finite and not negative",
+                );
+            }
+        }
+        Ok(())
+    }
 }
 
 impl Default for DomainProperties {
@@ -20,16 +280,29 @@ impl Default for DomainProperties {
             velocity_props: PackProperties {
                 advection: 0.1,
                 diffusion: 0.1,
+                scheme: AdvectionScheme::ForwardReverse,
             },
             pressure_props: PackProperties {
                 advection: 0.1,
                 diffusion: 0.1,
+                scheme: AdvectionScheme::ForwardReverse,
             },
+            pressure_channels: Vec::new(),
+            cell_size: 1.0,
+            origin: [0.0; 3],
             diffusion_steps: 1,
             step_delta_time: 1.0 / 10.0,
+            target_cfl: 1.0,
+            max_substeps: 64,
             velocity_decay: Some(0.1),
             pressure_acceleration: Some(0.1),
             vorticity: Some(0.1),
+            vorticity_mode: VorticityMode::Legacy,
+            buoyancy: None,
+            projection_iterations: 0,
+            projection_tolerance: 1e-4,
+            boundaries: Default::default(),
+            validation: None,
         }
     }
 }
@@ -0,0 +1,221 @@
+use std::fmt::{self, Debug};
+
+use crate::{
+    data::builder::check_properties, math::grid::Grid, Buoyancy, ChannelProperties, DomainError,
+    DomainProperties, GridDomain,
+};
+
+/// Single property changed by [`GridDomain::update_properties`], values are
+/// formatted with `Debug`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PropertyChange {
+    pub property: &'static str,
+    pub old: String,
+    pub new: String,
+}
+
+impl fmt::Display for PropertyChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {} -> {}", self.property, self.old, self.new)
+    }
+}
+
+/// Blend of properties in progress, advanced once per step.
+#[derive(Clone, Debug)]
+pub(crate) struct Transition {
+    from: DomainProperties,
+    to: DomainProperties,
+    step: usize,
+    steps: usize,
+}
+
+impl<const P_SIZE: usize, G: Grid> GridDomain<P_SIZE, G> {
+    /// Replaces properties, blending numeric ones over the next `steps`
+    /// steps so tuning does not pop. Everything else, `step_delta_time`
+    /// included, takes effect at once. Invalid properties are rejected and
+    /// leave the domain as it was.
+    ///
+    /// Every call of `simulate` counts as a step, substeps of
+    /// `simulate_for` included. Changes to `prop` made during a blend are
+    /// overwritten by it. Returns what differs from the previous target, in
+    /// declaration order.
+    pub fn update_properties(
+        &mut self,
+        prop: DomainProperties,
+        steps: usize,
+    ) -> Result<Vec<PropertyChange>, DomainError> {
+        check_properties::<P_SIZE>(&prop)?;
+        let previous = match &self.transition {
+            Some(t) => &t.to,
+            None => &self.prop,
+        };
+        let changes = diff(previous, &prop);
+        if steps <= 1 {
+            self.prop = prop;
+            self.transition = None;
+        } else {
+            self.prop.step_delta_time = prop.step_delta_time;
+            self.transition = Some(Transition {
+                from: self.prop.clone(),
+                to: prop,
+                step: 0,
+                steps,
+            });
+        }
+        Ok(changes)
+    }
+
+    /// Whether [`Self::update_properties`] is still blending.
+    pub fn is_blending(&self) -> bool {
+        self.transition.is_some()
+    }
+
+    /// Moves a blend on by one step, called before each step.
+    pub(crate) fn advance_transition(&mut self) {
+        let Some(t) = &mut self.transition else {
+            return;
+        };
+        t.step += 1;
+        if t.step >= t.steps {
+            let step_delta_time = self.prop.step_delta_time;
+            self.prop = self.transition.take().unwrap().to;
+            self.prop.step_delta_time = step_delta_time;
+        } else {
+            let blended = blend(&t.from, &t.to, t.step as f32 / t.steps as f32);
+            self.prop = DomainProperties {
+                step_delta_time: self.prop.step_delta_time,
+                ..blended
+            };
+        }
+    }
+}
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+fn lerp_steps(a: usize, b: usize, t: f32) -> usize {
+    lerp(a as f32, b as f32, t).round() as usize
+}
+
+// missing optional rates act as zero
+fn lerp_option(a: Option<f32>, b: Option<f32>, t: f32) -> Option<f32> {
+    match (a, b) {
+        (None, None) => None,
+        _ => Some(lerp(a.unwrap_or(0.0), b.unwrap_or(0.0), t)),
+    }
+}
+
+fn blend_channel(a: &ChannelProperties, b: &ChannelProperties, t: f32) -> ChannelProperties {
+    ChannelProperties {
+        advection: lerp(a.advection, b.advection, t),
+        diffusion: lerp(a.diffusion, b.diffusion, t),
+        pressure_weight: lerp(a.pressure_weight, b.pressure_weight, t),
+        decay: lerp_option(a.decay, b.decay, t),
+    }
+}
+
+fn blend_buoyancy(a: Option<&Buoyancy>, b: Option<&Buoyancy>, t: f32) -> Option<Buoyancy> {
+    // appearing or vanishing buoyancy fades its weights
+    let fade = |b: &Buoyancy, t: f32| Buoyancy {
+        alpha: b.alpha * t,
+        beta: b.beta * t,
+        ..b.clone()
+    };
+    match (a, b) {
+        (Some(a), Some(b)) if (a.temperature, a.density) == (b.temperature, b.density) => {
+            Some(Buoyancy {
+                ambient_temperature: lerp(a.ambient_temperature, b.ambient_temperature, t),
+                alpha: lerp(a.alpha, b.alpha, t),
+                beta: lerp(a.beta, b.beta, t),
+                gravity: std::array::from_fn(|i| lerp(a.gravity[i], b.gravity[i], t)),
+                ..b.clone()
+            })
+        }
+        (Some(a), None) => Some(fade(a, 1.0 - t)),
+        (None, Some(b)) => Some(fade(b, t)),
+        _ => b.cloned(),
+    }
+}
+
+/// Numeric properties part way from `a` to `b`, the rest taken from `b`.
+fn blend(a: &DomainProperties, b: &DomainProperties, t: f32) -> DomainProperties {
+    let mut prop = b.clone();
+    for (p, (a, b)) in [&mut prop.velocity_props, &mut prop.pressure_props]
+        .into_iter()
+        .zip([
+            (&a.velocity_props, &b.velocity_props),
+            (&a.pressure_props, &b.pressure_props),
+        ])
+    {
+        p.advection = lerp(a.advection, b.advection, t);
+        p.diffusion = lerp(a.diffusion, b.diffusion, t);
+    }
+    let channels = a.pressure_channels.len().max(b.pressure_channels.len());
+    prop.pressure_channels = (0..channels)
+        .map(|i| blend_channel(&a.channel(i), &b.channel(i), t))
+        .collect();
+    prop.cell_size = lerp(a.cell_size, b.cell_size, t);
+    prop.origin = std::array::from_fn(|i| lerp(a.origin[i], b.origin[i], t));
+    prop.diffusion_steps = lerp_steps(a.diffusion_steps, b.diffusion_steps, t);
+    prop.target_cfl = lerp(a.target_cfl, b.target_cfl, t);
+    prop.max_substeps = lerp_steps(a.max_substeps, b.max_substeps, t);
+    prop.velocity_decay = lerp_option(a.velocity_decay, b.velocity_decay, t);
+    prop.pressure_acceleration = lerp_option(a.pressure_acceleration, b.pressure_acceleration, t);
+    prop.vorticity = lerp_option(a.vorticity, b.vorticity, t);
+    prop.buoyancy = blend_buoyancy(a.buoyancy.as_ref(), b.buoyancy.as_ref(), t);
+    prop.projection_iterations = lerp_steps(a.projection_iterations, b.projection_iterations, t);
+    prop.projection_tolerance = lerp(a.projection_tolerance, b.projection_tolerance, t);
+    prop
+}
+
+fn diff(a: &DomainProperties, b: &DomainProperties) -> Vec<PropertyChange> {
+    let mut changes = Vec::new();
+    let mut check = |property, old: &dyn Debug, new: &dyn Debug| {
+        let (old, new) = (format!("{:?}", old), format!("{:?}", new));
+        if old != new {
+            changes.push(PropertyChange { property, old, new });
+        }
+    };
+    let (va, vb) = (&a.velocity_props, &b.velocity_props);
+    check("velocity_props.advection", &va.advection, &vb.advection);
+    check("velocity_props.diffusion", &va.diffusion, &vb.diffusion);
+    check("velocity_props.scheme", &va.scheme, &vb.scheme);
+    let (pa, pb) = (&a.pressure_props, &b.pressure_props);
+    check("pressure_props.advection", &pa.advection, &pb.advection);
+    check("pressure_props.diffusion", &pa.diffusion, &pb.diffusion);
+    check("pressure_props.scheme", &pa.scheme, &pb.scheme);
+    check(
+        "pressure_channels",
+        &a.pressure_channels,
+        &b.pressure_channels,
+    );
+    check("cell_size", &a.cell_size, &b.cell_size);
+    check("origin", &a.origin, &b.origin);
+    check("diffusion_steps", &a.diffusion_steps, &b.diffusion_steps);
+    check("step_delta_time", &a.step_delta_time, &b.step_delta_time);
+    check("target_cfl", &a.target_cfl, &b.target_cfl);
+    check("max_substeps", &a.max_substeps, &b.max_substeps);
+    check("velocity_decay", &a.velocity_decay, &b.velocity_decay);
+    check(
+        "pressure_acceleration",
+        &a.pressure_acceleration,
+        &b.pressure_acceleration,
+    );
+    check("vorticity", &a.vorticity, &b.vorticity);
+    check("vorticity_mode", &a.vorticity_mode, &b.vorticity_mode);
+    check("buoyancy", &a.buoyancy, &b.buoyancy);
+    check(
+        "projection_iterations",
+        &a.projection_iterations,
+        &b.projection_iterations,
+    );
+    check(
+        "projection_tolerance",
+        &a.projection_tolerance,
+        &b.projection_tolerance,
+    );
+    check("boundaries", &a.boundaries, &b.boundaries);
+    check("validation", &a.validation, &b.validation);
+    changes
+}
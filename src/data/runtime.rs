@@ -1,35 +1,76 @@
 use crate::{
-    algorithm::advection,
-    math::{swapchain::SwapchainPack, SizedArray3D},
+    algorithm::{advection, projection},
+    math::{grid::Grid, swapchain::SwapchainPack},
 };
 
 use super::flow;
 
-pub struct DomainRuntime<const P: usize, const SX: usize, const SY: usize, const SZ: usize> {
-    pub velocity: SwapchainPack<SizedArray3D<f32, SX, SY, SZ>, 3, 2>,
-    pub pressure: SwapchainPack<SizedArray3D<f32, SX, SY, SZ>, P, 2>,
-    pub blockage: SizedArray3D<flow::FlowFlags, SX, SY, SZ>,
+pub struct DomainRuntime<const P: usize, G: Grid> {
+    pub velocity: SwapchainPack<G::Array<f32>, 3, 2>,
+    pub pressure: SwapchainPack<G::Array<f32>, P, 2>,
+    pub blockage: G::Array<flow::FlowFlags>,
 }
 
-impl<const P: usize, const SX: usize, const SY: usize, const SZ: usize> Default
-    for DomainRuntime<P, SX, SY, SZ>
-{
-    fn default() -> Self {
+impl<const P: usize, G: Grid> DomainRuntime<P, G> {
+    pub fn new(grid: &G) -> Self {
         Self {
-            velocity: Default::default(),
-            pressure: Default::default(),
-            blockage: Default::default(),
+            velocity: SwapchainPack::new(grid.alloc()),
+            pressure: SwapchainPack::new(grid.alloc()),
+            blockage: grid.alloc(),
         }
     }
 }
 
-#[derive(Default)]
-pub(crate) struct DomainTemp<const SX: usize, const SY: usize, const SZ: usize> {
-    pub vorticies: SizedArray3D<f32, SX, SY, SZ>,
-    pub forward_velocity_coefficients: SizedArray3D<Option<advection::AdvectionResult>, SX, SY, SZ>,
-    pub reverse_velocity_coefficients: SizedArray3D<Option<advection::AdvectionResult>, SX, SY, SZ>,
-    pub pressure_coefficients: SizedArray3D<Option<advection::AdvectionResult>, SX, SY, SZ>,
-    pub forward_velocity_coefficients_totals: SizedArray3D<f32, SX, SY, SZ>,
-    pub reverse_velocity_coefficients_totals: SizedArray3D<f32, SX, SY, SZ>,
-    pub pressure_coefficients_totals: SizedArray3D<f32, SX, SY, SZ>,
+pub(crate) struct DomainTemp<G: Grid> {
+    pub vorticies: G::Array<f32>,
+    // allocated on first use, only the curl mode needs it
+    pub curl: Option<G::Array<[f32; 3]>>,
+    pub forward_velocity_coefficients: G::Array<Option<advection::AdvectionResult>>,
+    pub reverse_velocity_coefficients: G::Array<Option<advection::AdvectionResult>>,
+    pub pressure_coefficients: G::Array<Option<advection::AdvectionResult>>,
+    pub forward_velocity_coefficients_totals: G::Array<f32>,
+    pub reverse_velocity_coefficients_totals: G::Array<f32>,
+    pub pressure_coefficients_totals: G::Array<f32>,
+    // allocated on first use, projection is off by default
+    pub projection: Option<projection::ProjectionTemp<G::Array<f32>>>,
+    pub traces: Option<Traces<G>>,
+    // published fields before the step, velocity then pressure, only kept
+    // for rollback
+    pub backup: Vec<G::Array<f32>>,
+}
+
+impl<G: Grid> DomainTemp<G> {
+    pub fn new(grid: &G) -> Self {
+        Self {
+            vorticies: grid.alloc(),
+            curl: None,
+            forward_velocity_coefficients: grid.alloc(),
+            reverse_velocity_coefficients: grid.alloc(),
+            pressure_coefficients: grid.alloc(),
+            forward_velocity_coefficients_totals: grid.alloc(),
+            reverse_velocity_coefficients_totals: grid.alloc(),
+            pressure_coefficients_totals: grid.alloc(),
+            projection: None,
+            traces: None,
+            backup: Vec::new(),
+        }
+    }
+}
+
+/// Semi-Lagrangian trace ends of every cell, reused by both packs. Only
+/// allocated once a pack uses one of the traced schemes.
+pub(crate) struct Traces<G: Grid> {
+    pub departure: G::Array<[f32; 3]>,
+    pub arrival: G::Array<[f32; 3]>,
+    pub advected: G::Array<f32>,
+}
+
+impl<G: Grid> Traces<G> {
+    pub fn new(grid: &G) -> Self {
+        Self {
+            departure: grid.alloc(),
+            arrival: grid.alloc(),
+            advected: grid.alloc(),
+        }
+    }
 }
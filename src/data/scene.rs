@@ -0,0 +1,106 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    data::builder::check_shape, Coords, DomainBuilder, DomainError, DomainProperties, DynDomain,
+    DynGrid, Emitter, FlowFlags, Shape, Sink,
+};
+
+/// Text friendly description of a ready to run domain. Every field may be
+/// left out, missing properties take their defaults.
+///
+/// Any serde format works, e.g. a TOML scene:
+///
+/// ```toml
+/// size = [32, 64, 32]
+///
+/// [properties]
+/// vorticity = 0.3
+///
+/// [[obstacles]]
+/// shape = { Box = { from = [8, 0, 8], to = [24, 4, 24] } }
+///
+/// [[emitters]]
+/// shape = { Sphere = { center = [16.0, 8.0, 16.0], radius = 2.0 } }
+/// pressure = [0, 5.0]
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Scene {
+    pub size: Coords,
+    pub properties: DomainProperties,
+    pub obstacles: Vec<Obstacle>,
+    pub emitters: Vec<Emitter>,
+    pub sinks: Vec<Sink>,
+    /// Initial pressure, filled in order.
+    pub pressure: Vec<PressureFill>,
+    /// Initial velocity, filled in order.
+    pub velocity: Vec<VelocityFill>,
+}
+
+/// Faces blocked in every cell of a region, all of them by default.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Obstacle {
+    pub shape: Shape,
+    #[serde(default = "FlowFlags::all")]
+    pub faces: FlowFlags,
+}
+
+/// Pressure of every cell of a region, a value per channel.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PressureFill {
+    pub shape: Shape,
+    pub value: Vec<f32>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct VelocityFill {
+    pub shape: Shape,
+    pub value: [f32; 3],
+}
+
+impl Scene {
+    /// Builder set up with the scene, more can be added before building.
+    /// Errors when fills do not match the grid or the number of channels.
+    pub fn builder<const P_SIZE: usize>(
+        &self,
+    ) -> Result<DomainBuilder<P_SIZE, DynGrid>, DomainError> {
+        let size = self.size;
+        let mut builder = DynDomain::<P_SIZE>::builder(size).properties(self.properties.clone());
+        for o in &self.obstacles {
+            builder = builder.blocked(o.shape.clone(), o.faces);
+        }
+        for e in &self.emitters {
+            builder = builder.emitter(e.clone());
+        }
+        for s in &self.sinks {
+            builder = builder.sink(s.clone());
+        }
+        for (i, fill) in self.pressure.iter().enumerate() {
+            let value: [f32; P_SIZE] =
+                fill.value
+                    .as_slice()
+                    .try_into()
+                    .map_err(|_| DomainError::InvalidProperty {
+                        property: format!("pressure[{}].value", i),
+                        reason: "needs a value per pressure channel",
+                    })?;
+            check_shape(&fill.shape, size)?;
+            for c in fill.shape.cells(size) {
+                builder = builder.pressure(c, value);
+            }
+        }
+        for fill in &self.velocity {
+            check_shape(&fill.shape, size)?;
+            let [x, y, z] = fill.value;
+            for c in fill.shape.cells(size) {
+                builder = builder.velocity(c, (x, y, z));
+            }
+        }
+        Ok(builder)
+    }
+
+    /// Validated domain of the scene, see [`DomainBuilder::build`].
+    pub fn build<const P_SIZE: usize>(&self) -> Result<DynDomain<P_SIZE>, DomainError> {
+        self.builder()?.build()
+    }
+}
@@ -0,0 +1,208 @@
+use std::{
+    fmt,
+    io::{Read, Write},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    math::{grid::Grid, Flat3D},
+    Coords, DomainProperties, FlowFlags, GridDomain, Sized3D,
+};
+
+const MAGIC: [u8; 4] = *b"FLSN";
+/// Version of the snapshot layout, bumped on every incompatible change.
+pub const SNAPSHOT_VERSION: u32 = 8;
+
+/// Failure to save or restore a snapshot.
+#[derive(Debug)]
+pub enum SnapshotError {
+    Io(std::io::Error),
+    Encode(bincode::error::EncodeError),
+    Decode(bincode::error::DecodeError),
+    /// Stream does not start with a snapshot header.
+    NotSnapshot,
+    /// Snapshot was written by an unsupported version of the format.
+    Version(u32),
+    /// Snapshot holds a different number of pressure channels.
+    Channels {
+        expected: usize,
+        found: usize,
+    },
+    /// Snapshot holds a grid of different size.
+    Size {
+        expected: Coords,
+        found: Coords,
+    },
+    /// Field arrays do not match the header.
+    Corrupted,
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "snapshot i/o failed: {}", e),
+            Self::Encode(e) => write!(f, "snapshot encoding failed: {}", e),
+            Self::Decode(e) => write!(f, "snapshot decoding failed: {}", e),
+            Self::NotSnapshot => write!(f, "not a snapshot"),
+            Self::Version(v) => write!(f, "unsupported snapshot version {}", v),
+            Self::Channels { expected, found } => write!(
+                f,
+                "snapshot has {} pressure channels, expected {}",
+                found, expected
+            ),
+            Self::Size { expected, found } => {
+                write!(f, "snapshot grid is {:?}, expected {:?}", found, expected)
+            }
+            Self::Corrupted => write!(f, "snapshot fields do not match its header"),
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            Self::Encode(e) => Some(e),
+            Self::Decode(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for SnapshotError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl From<bincode::error::EncodeError> for SnapshotError {
+    fn from(e: bincode::error::EncodeError) -> Self {
+        Self::Encode(e)
+    }
+}
+
+impl From<bincode::error::DecodeError> for SnapshotError {
+    fn from(e: bincode::error::DecodeError) -> Self {
+        Self::Decode(e)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Header {
+    channels: usize,
+    size: Coords,
+}
+
+// Field arrays are written one after another in this order: velocity x, y, z,
+// pressure channels, blockage
+#[derive(Serialize)]
+struct Fields<'a> {
+    velocity: [&'a [f32]; 3],
+    pressure: Vec<&'a [f32]>,
+    blockage: Vec<u8>,
+}
+
+#[derive(Deserialize)]
+struct OwnedFields {
+    velocity: [Vec<f32>; 3],
+    pressure: Vec<Vec<f32>>,
+    blockage: Vec<u8>,
+}
+
+impl<const P_SIZE: usize, G: Grid> GridDomain<P_SIZE, G> {
+    /// Writes properties, published velocity and pressure, and blockage.
+    ///
+    /// Values set since the last `simulate` are not part of the snapshot.
+    pub fn save_snapshot<W: Write>(&self, mut w: W) -> Result<(), SnapshotError> {
+        let config = bincode::config::standard();
+        w.write_all(&MAGIC)?;
+        w.write_all(&SNAPSHOT_VERSION.to_le_bytes())?;
+        let header = Header {
+            channels: P_SIZE,
+            size: self.size(),
+        };
+        bincode::serde::encode_into_std_write(&header, &mut w, config)?;
+        bincode::serde::encode_into_std_write(&self.prop, &mut w, config)?;
+        let fields = Fields {
+            velocity: self
+                .data
+                .velocity
+                .chains()
+                .each_ref()
+                .map(|c| c.consumer().flat()),
+            pressure: self
+                .data
+                .pressure
+                .chains()
+                .iter()
+                .map(|c| c.consumer().flat())
+                .collect(),
+            blockage: self.data.blockage.flat().iter().map(|f| f.bits()).collect(),
+        };
+        bincode::serde::encode_into_std_write(&fields, &mut w, config)?;
+        Ok(())
+    }
+
+    /// Restores a snapshot written by `save_snapshot` into this domain.
+    ///
+    /// Domain is left untouched when the snapshot can not be read or does not
+    /// match its channel count or grid size.
+    pub fn load_snapshot<R: Read>(&mut self, mut r: R) -> Result<(), SnapshotError> {
+        let config = bincode::config::standard();
+        let mut magic = [0u8; 4];
+        r.read_exact(&mut magic)?;
+        if magic != MAGIC {
+            return Err(SnapshotError::NotSnapshot);
+        }
+        let mut version = [0u8; 4];
+        r.read_exact(&mut version)?;
+        let version = u32::from_le_bytes(version);
+        if version != SNAPSHOT_VERSION {
+            return Err(SnapshotError::Version(version));
+        }
+        let header: Header = bincode::serde::decode_from_std_read(&mut r, config)?;
+        if header.channels != P_SIZE {
+            return Err(SnapshotError::Channels {
+                expected: P_SIZE,
+                found: header.channels,
+            });
+        }
+        if header.size != self.size() {
+            return Err(SnapshotError::Size {
+                expected: self.size(),
+                found: header.size,
+            });
+        }
+        let prop: DomainProperties = bincode::serde::decode_from_std_read(&mut r, config)?;
+        let fields: OwnedFields = bincode::serde::decode_from_std_read(&mut r, config)?;
+        let Coords(x, y, z) = header.size;
+        let cells = x * y * z;
+        let complete = fields.pressure.len() == P_SIZE
+            && fields.blockage.len() == cells
+            && fields.velocity.iter().all(|v| v.len() == cells)
+            && fields.pressure.iter().all(|p| p.len() == cells);
+        if !complete {
+            return Err(SnapshotError::Corrupted);
+        }
+
+        self.prop = prop;
+        let chains = self.data.velocity.chains_mut().iter_mut();
+        for (chain, src) in chains.zip(&fields.velocity) {
+            for buffer in chain.data.iter_mut() {
+                buffer.flat_mut().copy_from_slice(src);
+            }
+        }
+        let chains = self.data.pressure.chains_mut().iter_mut();
+        for (chain, src) in chains.zip(&fields.pressure) {
+            for buffer in chain.data.iter_mut() {
+                buffer.flat_mut().copy_from_slice(src);
+            }
+        }
+        let blockage = self.data.blockage.flat_mut();
+        for (dst, bits) in blockage.iter_mut().zip(fields.blockage) {
+            *dst = FlowFlags::from_bits_truncate(bits);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,83 @@
+use rayon::prelude::*;
+
+use crate::{
+    math::{grid::Grid, Flat3D},
+    GridDomain, NonFiniteError, Recovery,
+};
+
+impl<const P_SIZE: usize, G: Grid> GridDomain<P_SIZE, G> {
+    /// Largest magnitude of the published velocity.
+    pub fn max_velocity(&self) -> f32 {
+        let [x, y, z] = self
+            .data
+            .velocity
+            .chains()
+            .each_ref()
+            .map(|c| c.consumer().flat());
+        x.par_iter()
+            .zip(y)
+            .zip(z)
+            .map(|((x, y), z)| x * x + y * y + z * z)
+            .reduce(|| 0.0, f32::max)
+            .sqrt()
+    }
+
+    /// Cells the fastest pack travels in a step of `step_delta_time` along
+    /// the published velocity.
+    pub fn cfl(&self) -> f32 {
+        self.cells_per_time() * self.prop.step_delta_time
+    }
+
+    /// Advances the simulation by `total_dt`, split into equal substeps short
+    /// enough to keep within `target_cfl`. The velocity is measured again
+    /// before each substep. Returns the number of substeps taken.
+    ///
+    /// `step_delta_time` is only changed for the duration of the call.
+    ///
+    /// Substeps run as [`Self::simulate_checked`], the first error is
+    /// returned once recovery policies that repair fields have finished the
+    /// interval. Policies that skip the rest of a step also skip the
+    /// remaining substeps.
+    pub fn simulate_for(&mut self, total_dt: f32) -> Result<usize, NonFiniteError> {
+        let step_delta_time = self.prop.step_delta_time;
+        let max_substeps = self.prop.max_substeps.max(1);
+        let mut remaining = total_dt;
+        let mut substeps = 0;
+        let mut first = None;
+        while remaining > 0.0 && substeps < max_substeps {
+            let cells = remaining * self.cells_per_time() / self.prop.target_cfl;
+            // rounding of `remaining` must not add a substep
+            let needed = (cells * (1.0 - 1e-4)).ceil();
+            // a non-finite velocity is not worth the whole budget
+            let left = (max_substeps - substeps) as f32;
+            let count = if needed.is_finite() {
+                needed.clamp(1.0, left)
+            } else {
+                1.0
+            };
+            let dt = remaining / count;
+            self.prop.step_delta_time = dt;
+            let result = self.simulate_checked();
+            remaining = if count <= 1.0 { 0.0 } else { remaining - dt };
+            substeps += 1;
+            if let Err(error) = result {
+                first.get_or_insert(error);
+                if matches!(
+                    self.prop.validation,
+                    Some(Recovery::Stop | Recovery::Rollback)
+                ) {
+                    break;
+                }
+            }
+        }
+        self.prop.step_delta_time = step_delta_time;
+        first.map_or(Ok(substeps), Err)
+    }
+
+    fn cells_per_time(&self) -> f32 {
+        let rate = (0..P_SIZE)
+            .map(|i| self.prop.channel(i).advection.abs())
+            .fold(self.prop.velocity_props.advection.abs(), f32::max);
+        self.max_velocity() * rate / self.prop.cell_size
+    }
+}
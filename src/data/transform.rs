@@ -0,0 +1,32 @@
+use crate::{math::grid::Grid, Coords, GridDomain, Sized3D};
+
+impl<const P_SIZE: usize, G: Grid> GridDomain<P_SIZE, G> {
+    /// Position in cell units, cell centres lie at whole coordinates.
+    pub fn world_to_cell(&self, pos: [f32; 3]) -> [f32; 3] {
+        let origin = self.prop.origin;
+        std::array::from_fn(|i| (pos[i] - origin[i]) / self.prop.cell_size)
+    }
+
+    /// World position of a position in cell units.
+    pub fn cell_to_world(&self, pos: [f32; 3]) -> [f32; 3] {
+        let origin = self.prop.origin;
+        std::array::from_fn(|i| origin[i] + pos[i] * self.prop.cell_size)
+    }
+
+    /// Cell containing the world position, if any.
+    pub fn world_to_coords(&self, pos: [f32; 3]) -> Option<Coords> {
+        let size = self.size();
+        let size = [size.0, size.1, size.2];
+        let cell = self.world_to_cell(pos).map(|v| (v + 0.5).floor());
+        let inside = cell
+            .iter()
+            .zip(size)
+            .all(|(v, len)| *v >= 0.0 && *v < len as f32);
+        inside.then(|| Coords(cell[0] as usize, cell[1] as usize, cell[2] as usize))
+    }
+
+    /// World position of the centre of the cell.
+    pub fn coords_to_world(&self, c: &Coords) -> [f32; 3] {
+        self.cell_to_world([c.0 as f32, c.1 as f32, c.2 as f32])
+    }
+}
@@ -0,0 +1,136 @@
+use std::fmt;
+
+use rayon::prelude::*;
+
+use crate::{
+    math::{grid::Grid, swapchain::Swapchain, Flat3D},
+    Coords, GridDomain, Recovery, Sized3D,
+};
+
+/// Stage of [`GridDomain::simulate`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Pass {
+    /// Values set by the user and emitters.
+    Input,
+    Diffusion,
+    Forces,
+    Projection,
+    Advection,
+}
+
+/// A pass left NaN or an infinity in a field.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NonFiniteError {
+    pub pass: Pass,
+    /// First offending cell, x varying fastest.
+    pub coords: Coords,
+}
+
+impl fmt::Display for NonFiniteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:?} pass left a non-finite value at ({}, {}, {})",
+            self.pass, self.coords.0, self.coords.1, self.coords.2
+        )
+    }
+}
+
+impl std::error::Error for NonFiniteError {}
+
+impl<const P_SIZE: usize, G: Grid> GridDomain<P_SIZE, G> {
+    fn chains_mut(&mut self) -> impl Iterator<Item = &mut Swapchain<G::Array<f32>, 2>> {
+        let velocity = self.data.velocity.chains_mut().iter_mut();
+        velocity.chain(self.data.pressure.chains_mut().iter_mut())
+    }
+
+    fn published(&self) -> impl Iterator<Item = &[f32]> {
+        let velocity = self.data.velocity.chains().iter();
+        velocity
+            .chain(self.data.pressure.chains().iter())
+            .map(|c| c.consumer().flat())
+    }
+
+    /// Keeps published fields for [`Recovery::Rollback`].
+    pub(crate) fn backup(&mut self) {
+        let chains = self.data.velocity.chains().iter();
+        let published = chains
+            .chain(self.data.pressure.chains())
+            .map(|c| c.consumer());
+        let backup = &mut self.temp.backup;
+        if backup.len() == 3 + P_SIZE {
+            // reuses allocations of the previous step
+            for (b, p) in backup.iter_mut().zip(published) {
+                b.clone_from(p);
+            }
+        } else {
+            *backup = published.cloned().collect();
+        }
+    }
+
+    /// Checks published fields after `pass` and recovers as set in
+    /// `validation`. Errors when the rest of the step has to be skipped,
+    /// otherwise keeps the first error of the step in `first`.
+    pub(crate) fn validate(
+        &mut self,
+        pass: Pass,
+        first: &mut Option<NonFiniteError>,
+    ) -> Result<(), NonFiniteError> {
+        let Some(recovery) = self.prop.validation else {
+            return Ok(());
+        };
+        let Some(index) = self
+            .published()
+            .filter_map(|f| f.par_iter().position_first(|v| !v.is_finite()))
+            .min()
+        else {
+            return Ok(());
+        };
+        let Coords(x, y, _) = self.size();
+        let coords = Coords(index % x, index / x % y, index / (x * y));
+        let error = NonFiniteError { pass, coords };
+
+        match recovery {
+            Recovery::Stop => return Err(error),
+            Recovery::Rollback => {
+                let backup = std::mem::take(&mut self.temp.backup);
+                for (chain, saved) in self.chains_mut().zip(&backup) {
+                    chain.consumer_mut().clone_from(saved);
+                }
+                self.temp.backup = backup;
+                self.republish();
+                return Err(error);
+            }
+            Recovery::Clamp(_) | Recovery::Zero => {
+                let mut bad = vec![false; self.published().next().map_or(0, |f| f.len())];
+                for field in self.published() {
+                    bad.par_iter_mut()
+                        .zip(field)
+                        .for_each(|(b, v)| *b |= !v.is_finite());
+                }
+                for chain in self.chains_mut() {
+                    let field = chain.consumer_mut().flat_mut();
+                    field.par_iter_mut().zip(&bad).for_each(|(v, b)| {
+                        if *b {
+                            *v = match recovery {
+                                Recovery::Clamp(limit) if !v.is_nan() => v.clamp(-limit, limit),
+                                _ => 0.0,
+                            };
+                        }
+                    });
+                }
+                self.republish();
+            }
+        }
+        first.get_or_insert(error);
+        Ok(())
+    }
+
+    // Write buffers start from the repaired published ones
+    fn republish(&mut self) {
+        for chain in self.chains_mut() {
+            let (r, w) = chain.rw_pair();
+            w.clone_from(r);
+        }
+    }
+}
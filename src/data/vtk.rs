@@ -0,0 +1,143 @@
+use std::{
+    fs::File,
+    io::{BufWriter, Error, ErrorKind, Result, Write},
+    path::PathBuf,
+};
+
+use crate::{
+    math::{grid::Grid, Flat3D},
+    Coords, GridDomain, Sized3D,
+};
+
+impl<const P_SIZE: usize, G: Grid> GridDomain<P_SIZE, G> {
+    /// Writes published fields as a legacy binary VTK structured points file:
+    /// `pressure_<channel>` scalars, `velocity` vectors and `blockage` face
+    /// flags as integers, one point per cell.
+    pub fn write_vtk<W: Write>(&self, w: W) -> Result<()> {
+        let mut w = BufWriter::new(w);
+        let Coords(x, y, z) = self.size();
+        let cells = x * y * z;
+        writeln!(w, "# vtk DataFile Version 3.0")?;
+        writeln!(w, "fluid_simulation domain")?;
+        writeln!(w, "BINARY")?;
+        writeln!(w, "DATASET STRUCTURED_POINTS")?;
+        writeln!(w, "DIMENSIONS {} {} {}", x, y, z)?;
+        let [ox, oy, oz] = self.prop.origin;
+        let h = self.prop.cell_size;
+        writeln!(w, "ORIGIN {} {} {}", ox, oy, oz)?;
+        writeln!(w, "SPACING {} {} {}", h, h, h)?;
+        writeln!(w, "POINT_DATA {}", cells)?;
+
+        // Binary data is big-endian, x varies fastest just like in our arrays
+        for (i, chain) in self.data.pressure.chains().iter().enumerate() {
+            writeln!(w, "SCALARS pressure_{} float 1", i)?;
+            writeln!(w, "LOOKUP_TABLE default")?;
+            for v in chain.consumer().flat() {
+                w.write_all(&v.to_be_bytes())?;
+            }
+            writeln!(w)?;
+        }
+
+        writeln!(w, "VECTORS velocity float")?;
+        let [vx, vy, vz] = self
+            .data
+            .velocity
+            .chains()
+            .each_ref()
+            .map(|c| c.consumer().flat());
+        for i in 0..cells {
+            for v in [vx[i], vy[i], vz[i]] {
+                w.write_all(&v.to_be_bytes())?;
+            }
+        }
+        writeln!(w)?;
+
+        writeln!(w, "SCALARS blockage int 1")?;
+        writeln!(w, "LOOKUP_TABLE default")?;
+        for f in self.data.blockage.flat() {
+            w.write_all(&i32::from(f.bits()).to_be_bytes())?;
+        }
+        writeln!(w)?;
+        w.flush()
+    }
+}
+
+/// Writes frames as numbered VTK files next to a `.vtk.series` index, which
+/// ParaView opens as a single time-dependent dataset.
+pub struct VtkSeries {
+    dir: PathBuf,
+    name: String,
+    frames: Vec<(String, f32)>,
+}
+
+impl VtkSeries {
+    /// Frames go to `dir/name_<frame>.vtk`, index to `dir/name.vtk.series`.
+    pub fn new(dir: impl Into<PathBuf>, name: &str) -> Self {
+        Self {
+            dir: dir.into(),
+            name: name.to_owned(),
+            frames: Vec::new(),
+        }
+    }
+
+    /// Appends domain as the frame at `time` and rewrites the index, so the
+    /// series stays readable if writing stops at any point. A non-finite
+    /// `time` has no JSON form and is rejected before anything is written.
+    pub fn write<const P_SIZE: usize, G: Grid>(
+        &mut self,
+        domain: &GridDomain<P_SIZE, G>,
+        time: f32,
+    ) -> Result<PathBuf> {
+        if !time.is_finite() {
+            return Err(Error::new(ErrorKind::InvalidInput, "time must be finite"));
+        }
+        let file = format!("{}_{:05}.vtk", self.name, self.frames.len());
+        let path = self.dir.join(&file);
+        domain.write_vtk(File::create(&path)?)?;
+        self.frames.push((file, time));
+        self.write_index()?;
+        Ok(path)
+    }
+
+    pub fn index_path(&self) -> PathBuf {
+        self.dir.join(format!("{}.vtk.series", self.name))
+    }
+
+    pub fn frames(&self) -> usize {
+        self.frames.len()
+    }
+
+    fn write_index(&self) -> Result<()> {
+        let mut w = BufWriter::new(File::create(self.index_path())?);
+        writeln!(w, "{{")?;
+        writeln!(w, "  \"file-series-version\": \"1.0\",")?;
+        writeln!(w, "  \"files\": [")?;
+        for (i, (file, time)) in self.frames.iter().enumerate() {
+            let separator = if i + 1 < self.frames.len() { "," } else { "" };
+            writeln!(
+                w,
+                "    {{ \"name\": \"{}\", \"time\": {} }}{}",
+                escape(file),
+                time,
+                separator
+            )?;
+        }
+        writeln!(w, "  ]")?;
+        writeln!(w, "}}")?;
+        w.flush()
+    }
+}
+
+/// Escapes quotes, backslashes and control characters for a JSON string.
+fn escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for ch in s.chars() {
+        match ch {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if c.is_control() => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out
+}
@@ -1,15 +1,34 @@
-#![feature(maybe_uninit_uninit_array, maybe_uninit_array_assume_init)]
-
 mod algorithm;
 mod data;
 mod math;
 mod support_utils;
 
-pub use data::properties::{DomainProperties, PackProperties};
-pub use data::{domain::Domain, flow::FlowFlags};
+pub use data::properties::{
+    AdvectionScheme, Boundaries, Boundary, Buoyancy, ChannelProperties, DomainProperties,
+    PackProperties, Recovery, VorticityMode,
+};
+#[cfg(feature = "serde")]
+pub use data::scene::{Obstacle, PressureFill, Scene, VelocityFill};
+#[cfg(feature = "serde")]
+pub use data::snapshot::{SnapshotError, SNAPSHOT_VERSION};
+pub use data::{
+    builder::DomainBuilder,
+    control::{Actuator, Controller, ControllerHandle, Measure},
+    diagnostics::{ChannelStats, Diagnostics},
+    domain::{Domain, DynDomain, GridDomain},
+    emitter::{Emitter, EmitterHandle, Shape, Sink},
+    error::DomainError,
+    flow::FlowFlags,
+    particles::{Collision, Integrator, Particle, ParticleSource, Particles},
+    probes::{Probe, ProbeSeries, Reading},
+    reload::PropertyChange,
+    validation::{NonFiniteError, Pass},
+    vtk::VtkSeries,
+};
+pub use math::grid::{DynGrid, FixedGrid, Grid};
 pub use math::swapchain::Swapchain;
-pub use math::{iterator, Coords, Sized3D, Slice3D, Slice3DMut};
 pub use math::Pid;
+pub use math::{iterator, Coords, Sized3D, Slice3D, Slice3DMut};
 
 #[macro_use]
 extern crate bitflags;
@@ -1,8 +1,5 @@
-use super::{
-    swapchain::{Swapchain, SwapchainPack},
-    Slice3D, Slice3DMut,
-};
-use crate::{support_utils, Coords};
+use super::{index, Flat3D, FlatIndex, Sized3D, Slice3D, Slice3DMut};
+use crate::Coords;
 
 #[derive(Clone, Debug)]
 pub struct Array3D<T> {
@@ -10,6 +7,57 @@ pub struct Array3D<T> {
     size: Coords,
 }
 
+impl<T> Array3D<T>
+where
+    T: Default + Clone,
+{
+    pub fn new(size: Coords) -> Self {
+        Self {
+            data: vec![Default::default(); size.0 * size.1 * size.2],
+            size,
+        }
+    }
+}
+
+impl<T> Sized3D for Array3D<T> {
+    fn size(&self) -> Coords {
+        self.size
+    }
+}
+
+impl<T> Flat3D for Array3D<T> {
+    type Item = T;
+
+    fn flat(&self) -> &[T] {
+        &self.data
+    }
+
+    fn flat_mut(&mut self) -> &mut [T] {
+        &mut self.data
+    }
+}
+
+impl<T> FlatIndex for Array3D<T> {
+    fn to_index(&self, c: &Coords) -> usize {
+        index(c.0, c.1, c.2, self.size.0, self.size.1, self.size.2)
+    }
+}
+
+impl<T> std::ops::Index<&Coords> for Array3D<T> {
+    type Output = T;
+
+    fn index(&self, index: &Coords) -> &Self::Output {
+        &self.data[self.to_index(index)]
+    }
+}
+
+impl<T> std::ops::IndexMut<&Coords> for Array3D<T> {
+    fn index_mut(&mut self, index: &Coords) -> &mut Self::Output {
+        let index = self.to_index(index);
+        &mut self.data[index]
+    }
+}
+
 impl<T> std::ops::Add for Array3D<T>
 where
     T: std::ops::AddAssign + Copy,
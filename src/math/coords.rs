@@ -1,10 +1,10 @@
-#[derive(Clone, Copy, Debug, PartialEq, PartialOrd, Eq, Ord)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, PartialOrd, Eq, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Coords(pub usize, pub usize, pub usize);
 
-#[derive(Clone, Copy, Debug, PartialEq, PartialOrd, Eq, Ord)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, PartialOrd, Eq, Ord, Hash)]
 pub struct CoordsDiff(pub isize, pub isize, pub isize);
 
-pub const ONES: CoordsDiff = CoordsDiff(1, 1, 1);
 pub const X_FORW: CoordsDiff = CoordsDiff(1, 0, 0);
 pub const Y_FORW: CoordsDiff = CoordsDiff(0, 1, 0);
 pub const Z_FORW: CoordsDiff = CoordsDiff(0, 0, 1);
@@ -12,15 +12,19 @@ pub const X_BACK: CoordsDiff = CoordsDiff(-1, 0, 0);
 pub const Y_BACK: CoordsDiff = CoordsDiff(0, -1, 0);
 pub const Z_BACK: CoordsDiff = CoordsDiff(0, 0, -1);
 
-impl Default for Coords {
-    fn default() -> Self {
-        Self(0, 0, 0)
+impl Coords {
+    /// Offsets coords, returning `None` if result leaves `0..size`.
+    pub fn checked_offset(&self, d: CoordsDiff, size: Coords) -> Option<Coords> {
+        let x = self.0.checked_add_signed(d.0).filter(|x| *x < size.0)?;
+        let y = self.1.checked_add_signed(d.1).filter(|y| *y < size.1)?;
+        let z = self.2.checked_add_signed(d.2).filter(|z| *z < size.2)?;
+        Some(Coords(x, y, z))
     }
 }
 
 impl From<CoordsDiff> for Coords {
     fn from(c: CoordsDiff) -> Self {
-        Self(c.0 as usize, c.1 as usize, c.1 as usize)
+        Self(c.0 as usize, c.1 as usize, c.2 as usize)
     }
 }
 
@@ -42,9 +46,9 @@ impl From<(isize, isize, isize)> for CoordsDiff {
     }
 }
 
-impl Into<(usize, usize, usize)> for Coords {
-    fn into(self) -> (usize, usize, usize) {
-        (self.0, self.1, self.2)
+impl From<Coords> for (usize, usize, usize) {
+    fn from(c: Coords) -> Self {
+        (c.0, c.1, c.2)
     }
 }
 
@@ -81,3 +85,9 @@ impl std::ops::Sub<CoordsDiff> for Coords {
         (diff - rhs).into()
     }
 }
+
+#[test]
+fn coords_diff_roundtrip() {
+    assert_eq!(Coords::from(CoordsDiff(1, 2, 3)), Coords(1, 2, 3));
+    assert_eq!(Coords(4, 5, 6) + CoordsDiff(0, 0, 1), Coords(4, 5, 7));
+}
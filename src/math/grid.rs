@@ -0,0 +1,51 @@
+use super::{Array3D, Flat3D, Sized3D, SizedArray3D, Slice3D, Slice3DMut};
+use crate::Coords;
+
+/// Describes the extent of a simulation grid and the array type used to store
+/// per-cell data on it.
+pub trait Grid: Sized3D + Clone + Send + Sync + 'static {
+    type Array<T: Clone + Default + Send + Sync + 'static>: for<'a> Slice3D<Output<'a> = &'a T>
+        + for<'a> Slice3DMut<Output<'a> = &'a mut T>
+        + Flat3D<Item = T>
+        + Clone
+        + Send
+        + Sync;
+
+    fn alloc<T: Clone + Default + Send + Sync + 'static>(&self) -> Self::Array<T>;
+}
+
+/// Grid with extent fixed at compile time.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FixedGrid<const X: usize, const Y: usize, const Z: usize>;
+
+impl<const X: usize, const Y: usize, const Z: usize> Sized3D for FixedGrid<X, Y, Z> {
+    fn size(&self) -> Coords {
+        Coords(X, Y, Z)
+    }
+}
+
+impl<const X: usize, const Y: usize, const Z: usize> Grid for FixedGrid<X, Y, Z> {
+    type Array<T: Clone + Default + Send + Sync + 'static> = SizedArray3D<T, X, Y, Z>;
+
+    fn alloc<T: Clone + Default + Send + Sync + 'static>(&self) -> Self::Array<T> {
+        Default::default()
+    }
+}
+
+/// Grid with extent chosen at runtime.
+#[derive(Clone, Copy, Debug)]
+pub struct DynGrid(pub Coords);
+
+impl Sized3D for DynGrid {
+    fn size(&self) -> Coords {
+        self.0
+    }
+}
+
+impl Grid for DynGrid {
+    type Array<T: Clone + Default + Send + Sync + 'static> = Array3D<T>;
+
+    fn alloc<T: Clone + Default + Send + Sync + 'static>(&self) -> Self::Array<T> {
+        Array3D::new(self.0)
+    }
+}
@@ -1,9 +1,13 @@
+pub mod array;
 pub mod coords;
+pub mod grid;
 pub mod iterator;
+pub mod parallel;
 pub mod sized_array;
 pub mod swapchain;
 pub mod pid;
 
+pub use array::Array3D;
 pub use coords::{Coords, CoordsDiff};
 pub use sized_array::SizedArray3D;
 pub use pid::Pid;
@@ -15,30 +19,32 @@ pub const fn index(x: usize, y: usize, z: usize, len_x: usize, len_y: usize, len
 
 pub trait FlatIndex {
     fn to_index(&self, c: &Coords) -> usize;
-    fn from_index(&self, i: usize) -> Coords;
 }
 
 pub trait Sized3D {
     fn size(&self) -> Coords;
 }
 
+/// Storage laid out as consecutive z-slabs of x-major rows.
+pub trait Flat3D: Sized3D {
+    type Item;
+    fn flat(&self) -> &[Self::Item];
+    fn flat_mut(&mut self) -> &mut [Self::Item];
+}
+
 impl FlatIndex for dyn Sized3D {
     fn to_index(&self, c: &Coords) -> usize {
         let Coords(lx, ly, lz) = self.size();
         index(c.0, c.1, c.2, lx, ly, lz)
     }
-
-    fn from_index(&self, _: usize) -> Coords {
-        todo!()
-    }
 }
 
 pub trait Slice3D {
     type Output<'a> where Self: 'a;
-    fn slice<'a, 'b>(&'a self, c: &'b Coords) -> Self::Output<'a>;
+    fn slice<'a>(&'a self, c: &Coords) -> Self::Output<'a>;
 }
 
 pub trait Slice3DMut {
     type Output<'a> where Self: 'a;
-    fn slice_mut<'a, 'b>(&'a mut self, c: &'b Coords) -> Self::Output<'a>;
+    fn slice_mut<'a>(&'a mut self, c: &Coords) -> Self::Output<'a>;
 }
@@ -0,0 +1,142 @@
+use rayon::prelude::*;
+
+use super::Flat3D;
+use crate::Coords;
+
+/// Number of z-slabs handled by a single task when scattering. Fixed, so the
+/// summation order (and therefore the result) does not depend on the number
+/// of threads.
+const SCATTER_BLOCK: usize = 8;
+
+/// Sets every cell of `dst` from `f`, z-slabs run in parallel.
+pub fn for_each_mut<DST, F>(dst: &mut DST, f: F)
+where
+    DST: Flat3D,
+    DST::Item: Send,
+    F: Fn(&Coords, &mut DST::Item) + Sync,
+{
+    let Coords(x, y, _) = dst.size();
+    if x * y == 0 {
+        return;
+    }
+    dst.flat_mut()
+        .par_chunks_mut(x * y)
+        .enumerate()
+        .for_each(|(z, slab)| {
+            for (i, v) in slab.iter_mut().enumerate() {
+                f(&Coords(i % x, i / x, z), v);
+            }
+        });
+}
+
+/// Sets every cell of three fields of the same size at once, so work shared
+/// by the components of a cell is done only once.
+pub fn for_each_mut3<DST, F>(dst: [&mut DST; 3], f: F)
+where
+    DST: Flat3D,
+    DST::Item: Send,
+    F: Fn(&Coords, [&mut DST::Item; 3]) + Sync,
+{
+    let Coords(x, y, _) = dst[0].size();
+    if x * y == 0 {
+        return;
+    }
+    let [a, b, c] = dst;
+    a.flat_mut()
+        .par_chunks_mut(x * y)
+        .zip(b.flat_mut().par_chunks_mut(x * y))
+        .zip(c.flat_mut().par_chunks_mut(x * y))
+        .enumerate()
+        .for_each(|(z, ((a, b), c))| {
+            let cells = a.iter_mut().zip(b.iter_mut()).zip(c.iter_mut());
+            for (i, ((a, b), c)) in cells.enumerate() {
+                f(&Coords(i % x, i / x, z), [a, b, c]);
+            }
+        });
+}
+
+/// Dot product summed per z-slab, then over slabs in order.
+pub fn dot<A: Flat3D<Item = f32>>(a: &A, b: &A) -> f32 {
+    let Coords(x, y, _) = a.size();
+    if x * y == 0 {
+        return 0.0;
+    }
+    a.flat()
+        .par_chunks(x * y)
+        .zip(b.flat().par_chunks(x * y))
+        .map(|(a, b)| a.iter().zip(b).fold(0.0, |acc, (a, b)| acc + a * b))
+        .collect::<Vec<f32>>()
+        .into_iter()
+        .sum()
+}
+
+/// Accumulates values around a block of z-slabs.
+pub struct Scatter {
+    window: Vec<f32>,
+    size: Coords,
+    first: usize,
+    reach: usize,
+}
+
+impl Scatter {
+    /// Adds value to the cell, which must be within `reach` slabs of the block.
+    pub fn add(&mut self, c: &Coords, v: f32) {
+        let Coords(x, y, z) = self.size;
+        let slab = (c.2 + z + self.reach - self.first) % z;
+        self.window[c.0 + x * (c.1 + y * slab)] += v;
+    }
+}
+
+/// Calls `f` for every cell of `dst`, letting it add values to cells up to
+/// `reach` z-slabs away. Blocks of slabs run in parallel into private windows
+/// which are then added to `dst` in block order.
+pub fn scatter<DST, F>(dst: &mut DST, reach: usize, f: F)
+where
+    DST: Flat3D<Item = f32>,
+    F: Fn(&Coords, &mut Scatter) + Sync,
+{
+    let size = dst.size();
+    let Coords(x, y, z) = size;
+    let slab = x * y;
+    if slab * z == 0 {
+        return;
+    }
+    let window_len = z.min(SCATTER_BLOCK + 2 * reach);
+    let blocks: Vec<Scatter> = (0..z)
+        .step_by(SCATTER_BLOCK)
+        .collect::<Vec<_>>()
+        .into_par_iter()
+        .map(|first| {
+            let mut scatter = Scatter {
+                window: vec![0.0; window_len * slab],
+                size,
+                first,
+                reach,
+            };
+            for cz in first..(first + SCATTER_BLOCK).min(z) {
+                for cy in 0..y {
+                    for cx in 0..x {
+                        f(&Coords(cx, cy, cz), &mut scatter);
+                    }
+                }
+            }
+            scatter
+        })
+        .collect();
+
+    dst.flat_mut()
+        .par_chunks_mut(slab)
+        .enumerate()
+        .for_each(|(cz, dst)| {
+            let sources: Vec<&[f32]> = blocks
+                .iter()
+                .filter_map(|b| {
+                    let w = (cz + z + reach - b.first) % z;
+                    (w < window_len).then(|| &b.window[w * slab..(w + 1) * slab])
+                })
+                .collect();
+            for (i, v) in dst.iter_mut().enumerate() {
+                *v += sources.iter().fold(0.0, |a, s| a + s[i]);
+            }
+        });
+}
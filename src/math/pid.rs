@@ -1,3 +1,4 @@
+#[derive(Debug)]
 pub struct Pid {
     pub kp: f32,
     pub ki: f32,
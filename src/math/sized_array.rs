@@ -1,5 +1,5 @@
 use super::{
-    index, FlatIndex, Sized3D, Slice3D, Slice3DMut,
+    index, Flat3D, FlatIndex, Sized3D, Slice3D, Slice3DMut,
 };
 use crate::Coords;
 
@@ -21,14 +21,22 @@ impl<T, const X: usize, const Y: usize, const Z: usize> Sized3D for SizedArray3D
     }
 }
 
+impl<T, const X: usize, const Y: usize, const Z: usize> Flat3D for SizedArray3D<T, X, Y, Z> {
+    type Item = T;
+
+    fn flat(&self) -> &[T] {
+        &self.0
+    }
+
+    fn flat_mut(&mut self) -> &mut [T] {
+        &mut self.0
+    }
+}
+
 impl<T, const X: usize, const Y: usize, const Z: usize> FlatIndex for SizedArray3D<T, X, Y, Z> {
     fn to_index(&self, c: &Coords) -> usize {
         index(c.0, c.1, c.2, X, Y, Z)
     }
-
-    fn from_index(&self, _: usize) -> Coords {
-        todo!()
-    }
 }
 
 impl<T, const X: usize, const Y: usize, const Z: usize> std::ops::Index<&Coords>
@@ -37,7 +45,7 @@ impl<T, const X: usize, const Y: usize, const Z: usize> std::ops::Index<&Coords>
     type Output = T;
 
     fn index(&self, index: &Coords) -> &Self::Output {
-        &self.0[self.to_index(&index)]
+        &self.0[self.to_index(index)]
     }
 }
 
@@ -45,7 +53,7 @@ impl<T, const X: usize, const Y: usize, const Z: usize> std::ops::IndexMut<&Coor
     for SizedArray3D<T, X, Y, Z>
 {
     fn index_mut(&mut self, index: &Coords) -> &mut Self::Output {
-        let index = self.to_index(&index);
+        let index = self.to_index(index);
         &mut self.0[index]
     }
 }
@@ -26,25 +26,42 @@ where
     }
 }
 
+impl<T, const SIZE: usize> Swapchain<T, SIZE>
+where
+    T: Clone,
+{
+    pub fn new(init: T) -> Self {
+        Self {
+            data: std::array::from_fn(|_| init.clone()),
+            current_consumer: 0,
+            current_producer: 1,
+        }
+    }
+}
+
 impl<T, const SIZE: usize> Swapchain<T, SIZE> {
     pub fn consumer(&self) -> &T {
         &self.data[self.current_consumer]
     }
 
+    pub fn consumer_mut(&mut self) -> &mut T {
+        &mut self.data[self.current_consumer]
+    }
+
     pub fn producer(&mut self) -> &mut T {
         &mut self.data[self.current_producer]
     }
 
-    pub fn rw_pair<'a>(&'a mut self) -> (&'a T, &'a mut T) {
+    pub fn rw_pair(&mut self) -> (&T, &mut T) {
         let w_idx = self.current_producer;
         let r_idx = self.current_consumer;
         assert_ne!(w_idx, r_idx);
         if w_idx > r_idx {
             let (rs, ws) = self.data.split_at_mut(w_idx);
-            return (rs.last().unwrap(), ws.first_mut().unwrap());
+            (&rs[r_idx], &mut ws[0])
         } else {
             let (ws, rs) = self.data.split_at_mut(r_idx);
-            return (rs.first().unwrap(), ws.last_mut().unwrap());
+            (&rs[0], &mut ws[w_idx])
         }
     }
 }
@@ -65,7 +82,7 @@ where
     type Output<'a> = T::Output<'a> where Self: 'a;
 
     fn slice<'a>(&'a self, c: &Coords) -> Self::Output<'a> {
-        self.consumer().slice(&c)
+        self.consumer().slice(c)
     }
 }
 
@@ -76,7 +93,7 @@ where
     type Output<'a> = T::Output<'a> where Self: 'a;
 
     fn slice_mut<'a>(&'a mut self, c: &Coords) -> Self::Output<'a> {
-        self.producer().slice_mut(&c)
+        self.producer().slice_mut(c)
     }
 }
 
@@ -85,12 +102,12 @@ where
     T: std::clone::Clone,
 {
     fn swap_buffers(&mut self) {
-        // Apply write buffer to read
-        let (r, w) = self.rw_pair();
-        *w = r.clone();
         // Rotate indexes
         self.current_producer = (self.current_producer + 1) % SIZE;
         self.current_consumer = (self.current_consumer + 1) % SIZE;
+        // Start next write buffer from the freshly published read buffer
+        let (r, w) = self.rw_pair();
+        w.clone_from(r);
     }
 }
 
@@ -98,10 +115,29 @@ pub struct SwapchainPack<T, const PACK_SIZE: usize, const SW_SIZE: usize> {
     data: [Swapchain<T, SW_SIZE>; PACK_SIZE],
 }
 
+impl<T, const PACK_SIZE: usize, const SW_SIZE: usize> SwapchainPack<T, PACK_SIZE, SW_SIZE>
+where
+    T: Clone,
+{
+    pub fn new(init: T) -> Self {
+        Self {
+            data: std::array::from_fn(|_| Swapchain::new(init.clone())),
+        }
+    }
+}
+
 impl<T, const PACK_SIZE: usize, const SW_SIZE: usize> SwapchainPack<T, PACK_SIZE, SW_SIZE> {
-    pub fn rw_pairs<'a>(&'a mut self) -> [(&'a T, &'a mut T); PACK_SIZE] {
+    pub fn rw_pairs(&mut self) -> [(&T, &mut T); PACK_SIZE] {
         self.data.each_mut().map(|p| p.rw_pair())
     }
+
+    pub fn chains(&self) -> &[Swapchain<T, SW_SIZE>; PACK_SIZE] {
+        &self.data
+    }
+
+    pub fn chains_mut(&mut self) -> &mut [Swapchain<T, SW_SIZE>; PACK_SIZE] {
+        &mut self.data
+    }
 }
 
 impl<T, const PACK_SIZE: usize, const SW_SIZE: usize> Default
@@ -150,7 +186,7 @@ where
     }
 }
 
-impl<'a, T, const PACK_SIZE: usize, const SW_SIZE: usize> Sized3D
+impl<T, const PACK_SIZE: usize, const SW_SIZE: usize> Sized3D
     for SwapchainPack<T, PACK_SIZE, SW_SIZE>
 where
     T: Sized3D,
@@ -160,3 +196,22 @@ where
         self.data[0].size()
     }
 }
+
+#[test]
+fn swap_publishes_producer() {
+    let mut chain: Swapchain<i32, 2> = Default::default();
+    *chain.producer() = 7;
+    chain.swap_buffers();
+    assert_eq!(*chain.consumer(), 7);
+    // the next producer starts from the published value
+    assert_eq!(*chain.producer(), 7);
+
+    let mut chain: Swapchain<i32, 3> = Default::default();
+    for n in 1..=6 {
+        *chain.producer() += n;
+        chain.swap_buffers();
+        assert_eq!(*chain.consumer(), n * (n + 1) / 2, "swap {n}");
+        let (r, w) = chain.rw_pair();
+        assert_eq!(*r, *w);
+    }
+}
@@ -0,0 +1,62 @@
+use fluid_simulation::{iterator, Coords, Domain, FlowFlags, Sized3D};
+
+#[test]
+fn blockage_faces_stay_consistent() {
+    let mut domain: Domain<1, 6, 6, 6> = Default::default();
+    domain.fill_box(&Coords(1, 1, 1), &Coords(3, 3, 3), true);
+    domain.fill_sphere([4.0, 4.0, 4.0], 1.0, true);
+    domain.set_wall(&Coords(0, 5, 5), &Coords(0, 5, 4), true);
+    domain.set_solid(&Coords(2, 2, 2), false);
+    assert!(domain.is_solid(&Coords(1, 1, 1)));
+    assert!(domain.is_solid(&Coords(4, 4, 4)));
+    assert!(!domain.is_solid(&Coords(2, 2, 2)));
+    // cleared cell is still enclosed by its solid neighbours
+    assert!(domain
+        .blocked_faces(&Coords(2, 2, 2))
+        .contains(FlowFlags::X_BACK | FlowFlags::Y_BACK));
+    assert!(!domain
+        .blocked_faces(&Coords(2, 2, 2))
+        .contains(FlowFlags::X_FORW));
+    assert_eq!(domain.blocked_faces(&Coords(0, 5, 5)), FlowFlags::Z_BACK);
+
+    let size = domain.size();
+    for c in iterator::iterate(size) {
+        let blk = domain.blocked_faces(&c);
+        for (face, n) in [
+            (FlowFlags::X_FORW, Coords(c.0 + 1, c.1, c.2)),
+            (FlowFlags::Y_FORW, Coords(c.0, c.1 + 1, c.2)),
+            (FlowFlags::Z_FORW, Coords(c.0, c.1, c.2 + 1)),
+        ] {
+            if n.0 < size.0 && n.1 < size.1 && n.2 < size.2 {
+                assert_eq!(
+                    blk.contains(face),
+                    domain.blocked_faces(&n).contains(face.opposite()),
+                    "{:?} -> {:?}",
+                    c,
+                    n
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn wall_stops_diffusion() {
+    let mut domain: Domain<1, 2, 1, 1> = Default::default();
+    domain.prop.pressure_acceleration = None;
+    domain.prop.vorticity = None;
+    domain.set_wall(&Coords(0, 0, 0), &Coords(1, 0, 0), true);
+    domain.set_pressure(&Coords(0, 0, 0), &[1.0]);
+    for _ in 0..10 {
+        domain.simulate();
+    }
+    assert_eq!(domain.pressure(&Coords(0, 0, 0)), [1.0]);
+    assert_eq!(domain.pressure(&Coords(1, 0, 0)), [0.0]);
+}
+
+#[test]
+#[should_panic(expected = "cells must share a face")]
+fn wall_needs_adjacent_cells() {
+    let mut domain: Domain<1, 4, 4, 4> = Default::default();
+    domain.set_wall(&Coords(0, 0, 0), &Coords(2, 0, 0), true);
+}
@@ -0,0 +1,49 @@
+use fluid_simulation::{iterator, Boundaries, Boundary, Coords, Domain, Sized3D};
+
+fn total<const X: usize, const Y: usize, const Z: usize>(domain: &Domain<1, X, Y, Z>) -> f32 {
+    iterator::iterate(domain.size()).fold(0.0, |a, c| a + domain.pressure(&c)[0])
+}
+
+#[test]
+fn outflow_drains_domain() {
+    let mut domain: Domain<1, 4, 4, 4> = Default::default();
+    domain.prop.boundaries = Boundaries::all(Boundary::Outflow);
+    domain.set_pressure(&Coords(0, 1, 1), &[10.0]);
+    domain.simulate();
+    let mut last = total(&domain);
+    assert!(last < 10.0);
+    for _ in 0..10 {
+        domain.simulate();
+        let sum = total(&domain);
+        assert!(sum < last, "{} >= {}", sum, last);
+        last = sum;
+    }
+}
+
+#[test]
+fn periodic_wraps_around() {
+    let mut domain: Domain<1, 4, 1, 1> = Default::default();
+    domain.prop.boundaries.x_forw = Boundary::Periodic;
+    domain.prop.boundaries.x_back = Boundary::Periodic;
+    domain.set_pressure(&Coords(0, 0, 0), &[1.0]);
+    domain.simulate();
+    assert!(domain.pressure(&Coords(3, 0, 0))[0] > 0.0);
+    let [left] = domain.pressure(&Coords(3, 0, 0));
+    let [right] = domain.pressure(&Coords(1, 0, 0));
+    assert!((left - right).abs() < 1e-6, "{} != {}", left, right);
+    assert!((total(&domain) - 1.0).abs() < 1e-6);
+}
+
+#[test]
+fn inflow_feeds_domain() {
+    let mut domain: Domain<1, 4, 4, 4> = Default::default();
+    domain.prop.boundaries.x_back = Boundary::Inflow {
+        pressure: vec![1.0],
+        velocity: [1.0, 0.0, 0.0],
+    };
+    for _ in 0..10 {
+        domain.simulate();
+    }
+    assert!(domain.pressure(&Coords(0, 2, 2))[0] > domain.pressure(&Coords(3, 2, 2))[0]);
+    assert!(domain.velocity(&Coords(0, 2, 2)).0 > 0.0);
+}
@@ -0,0 +1,131 @@
+use fluid_simulation::{
+    Boundaries, Boundary, Buoyancy, Coords, Domain, DomainError, DomainProperties, DynDomain, Shape,
+};
+
+#[test]
+fn initial_fields_are_published() {
+    let domain = Domain::<2, 4, 4, 4>::builder()
+        .solid(Shape::Point(Coords(3, 3, 3)))
+        .pressure(Coords(1, 2, 3), [1.0, 2.0])
+        .velocity(Coords(0, 1, 2), (1.0, 0.0, -1.0))
+        .build()
+        .unwrap();
+    assert_eq!(domain.pressure(&Coords(1, 2, 3)), [1.0, 2.0]);
+    assert_eq!(domain.velocity(&Coords(0, 1, 2)), (1.0, 0.0, -1.0));
+    assert!(domain.is_solid(&Coords(3, 3, 3)));
+    assert!(!domain.is_solid(&Coords(2, 3, 3)));
+}
+
+#[test]
+fn keeps_validated_config() {
+    let mut prop = DomainProperties {
+        step_delta_time: 0.25,
+        ..Default::default()
+    };
+    let mut domain = DynDomain::<1>::builder(Coords(3, 3, 3))
+        .properties(prop.clone())
+        .boundaries(Boundaries::all(Boundary::Outflow))
+        .build()
+        .unwrap();
+    prop.boundaries = Boundaries::all(Boundary::Outflow);
+    assert_eq!(domain.config(), Some(&prop));
+    assert_eq!(domain.prop, prop);
+
+    // later changes leave the validated copy alone
+    domain.prop.step_delta_time = 1.0;
+    assert_eq!(domain.config().unwrap().step_delta_time, 0.25);
+    assert_eq!(Domain::<1, 2, 2, 2>::default().config(), None);
+}
+
+#[test]
+fn rejects_invalid_properties() {
+    let prop = DomainProperties {
+        step_delta_time: -1.0,
+        ..Default::default()
+    };
+    let error = Domain::<1, 4, 4, 4>::builder()
+        .properties(prop)
+        .build()
+        .err()
+        .unwrap();
+    assert!(
+        matches!(&error, DomainError::InvalidProperty { property, .. } if property == "step_delta_time"),
+        "{}",
+        error
+    );
+}
+
+#[test]
+fn rejects_missing_buoyancy_channels() {
+    let prop = DomainProperties {
+        buoyancy: Some(Buoyancy {
+            temperature: 1,
+            density: 0,
+            ambient_temperature: 0.0,
+            alpha: 1.0,
+            beta: 1.0,
+            gravity: [0.0, -1.0, 0.0],
+        }),
+        ..Default::default()
+    };
+    let error = Domain::<1, 4, 4, 4>::builder()
+        .properties(prop)
+        .build()
+        .err()
+        .unwrap();
+    assert!(
+        matches!(&error, DomainError::InvalidProperty { property, .. } if property == "buoyancy.temperature"),
+        "{}",
+        error
+    );
+}
+
+#[test]
+fn rejects_cells_outside_of_grid() {
+    let size = Coords(4, 4, 4);
+    let error = Domain::<1, 4, 4, 4>::builder()
+        .velocity(Coords(0, 4, 0), (1.0, 0.0, 0.0))
+        .build()
+        .err();
+    let coords = Coords(0, 4, 0);
+    assert_eq!(error, Some(DomainError::OutOfBounds { coords, size }));
+
+    let to = Coords(2, 2, 5);
+    let error = Domain::<1, 4, 4, 4>::builder()
+        .solid(Shape::Box {
+            from: Coords(0, 0, 0),
+            to,
+        })
+        .build()
+        .err();
+    assert_eq!(error, Some(DomainError::OutOfBounds { coords: to, size }));
+}
+
+#[test]
+fn rejects_invalid_boundaries() {
+    let property = |b: Boundaries| match Domain::<1, 4, 4, 4>::builder().boundaries(b).build() {
+        Err(DomainError::InvalidProperty { property, .. }) => property,
+        other => panic!("{:?}", other.err()),
+    };
+    let lone = Boundaries {
+        y_back: Boundary::Periodic,
+        ..Default::default()
+    };
+    assert_eq!(property(lone), "boundaries.y_back");
+
+    let inflow = Boundaries::all(Boundary::Inflow {
+        pressure: vec![1.0, 2.0],
+        velocity: [0.0; 3],
+    });
+    assert_eq!(property(inflow), "boundaries.x_forw");
+
+    let wrap = Boundaries {
+        z_forw: Boundary::Periodic,
+        z_back: Boundary::Periodic,
+        ..Default::default()
+    };
+    assert!(Domain::<1, 4, 4, 4>::builder()
+        .boundaries(wrap)
+        .build()
+        .is_ok());
+}
@@ -0,0 +1,62 @@
+use fluid_simulation::{Buoyancy, Coords};
+
+mod common;
+
+fn buoyancy() -> Buoyancy {
+    Buoyancy {
+        temperature: 0,
+        density: 1,
+        ambient_temperature: 1.0,
+        alpha: 0.5,
+        beta: 2.0,
+        gravity: [0.0, -4.0, 0.0],
+    }
+}
+
+#[test]
+fn hot_gas_rises_and_dense_smoke_sinks() {
+    let mut domain = common::quiet::<2, 4, 4, 4>();
+    domain.prop.step_delta_time = 0.5;
+    domain.prop.buoyancy = Some(buoyancy());
+    let (hot, dense, solid) = (Coords(1, 1, 1), Coords(2, 2, 2), Coords(3, 0, 0));
+    for c in [Coords(0, 0, 0), solid] {
+        domain.set_pressure(&c, &[1.0, 0.0]);
+    }
+    domain.set_pressure(&hot, &[2.0, 1.0]);
+    domain.set_pressure(&dense, &[1.0, 2.0]);
+    domain.set_pressure(&solid, &[5.0, 0.0]);
+    domain.set_solid(&solid, true);
+    domain.simulate();
+
+    // (0.5 * 1 - 2 * (2 - 1)) * -4 * 0.5
+    assert_eq!(domain.velocity(&hot), (0.0, 3.0, 0.0));
+    // 0.5 * 2 * -4 * 0.5
+    assert_eq!(domain.velocity(&dense), (0.0, -2.0, 0.0));
+    assert_eq!(domain.velocity(&Coords(0, 0, 0)), (0.0, 0.0, 0.0));
+    assert_eq!(domain.velocity(&solid), (0.0, 0.0, 0.0));
+}
+
+#[test]
+fn gravity_sets_direction() {
+    let mut domain = common::quiet::<2, 4, 4, 4>();
+    domain.prop.step_delta_time = 0.5;
+    domain.prop.buoyancy = Some(Buoyancy {
+        gravity: [1.0, 0.0, -1.0],
+        ..buoyancy()
+    });
+    domain.set_pressure(&Coords(1, 1, 1), &[3.0, 0.0]);
+    domain.simulate();
+    assert_eq!(domain.velocity(&Coords(1, 1, 1)), (-2.0, 0.0, 2.0));
+}
+
+#[test]
+fn missing_channels_skip_buoyancy() {
+    let mut domain = common::quiet::<2, 4, 4, 4>();
+    domain.prop.buoyancy = Some(Buoyancy {
+        density: 2,
+        ..buoyancy()
+    });
+    domain.set_pressure(&Coords(1, 1, 1), &[3.0, 0.0]);
+    domain.simulate();
+    assert_eq!(domain.velocity(&Coords(1, 1, 1)), (0.0, 0.0, 0.0));
+}
@@ -0,0 +1,82 @@
+use fluid_simulation::{iterator, AdvectionScheme, ChannelProperties, Coords, Domain, Sized3D};
+
+mod common;
+
+fn channel(pressure_weight: f32, decay: Option<f32>) -> ChannelProperties {
+    ChannelProperties {
+        advection: 0.0,
+        diffusion: 0.0,
+        pressure_weight,
+        decay,
+    }
+}
+
+fn speed(domain: &Domain<2, 6, 6, 6>) -> f32 {
+    iterator::iterate(domain.size())
+        .map(|c| {
+            let (x, y, z) = domain.velocity(&c);
+            x.abs() + y.abs() + z.abs()
+        })
+        .sum()
+}
+
+#[test]
+fn passive_channel_does_not_push() {
+    let mut domain = common::quiet::<2, 6, 6, 6>();
+    domain.prop.step_delta_time = 1.0;
+    domain.prop.pressure_acceleration = Some(1.0);
+    domain.prop.pressure_channels = vec![channel(1.0, None), channel(0.0, None)];
+    domain.set_pressure(&Coords(3, 3, 3), &[0.0, 5.0]);
+    domain.simulate();
+    domain.simulate();
+    assert_eq!(speed(&domain), 0.0);
+
+    domain.set_pressure(&Coords(3, 3, 3), &[5.0, 5.0]);
+    domain.simulate();
+    domain.simulate();
+    assert!(speed(&domain) > 0.0);
+}
+
+#[test]
+fn channels_decay_separately() {
+    let mut domain = common::quiet::<2, 6, 6, 6>();
+    domain.prop.step_delta_time = 1.0;
+    domain.prop.pressure_channels = vec![channel(1.0, Some(0.5)), channel(1.0, None)];
+    domain.set_pressure(&Coords(1, 2, 3), &[4.0, 4.0]);
+    domain.simulate();
+    assert_eq!(domain.pressure(&Coords(1, 2, 3)), [2.0, 4.0]);
+}
+
+#[test]
+fn channels_move_at_own_rates() {
+    let mut domain = common::quiet::<2, 6, 6, 6>();
+    domain.prop.step_delta_time = 1.0;
+    domain.prop.diffusion_steps = 1;
+    domain.prop.pressure_props.scheme = AdvectionScheme::SemiLagrangian;
+    domain.prop.pressure_channels = vec![
+        ChannelProperties {
+            advection: 1.0,
+            diffusion: 0.0,
+            ..channel(1.0, None)
+        },
+        ChannelProperties {
+            advection: 0.0,
+            diffusion: 0.5,
+            ..channel(1.0, None)
+        },
+    ];
+    for c in iterator::iterate(domain.size()) {
+        domain.set_velocity(&c, (1.0, 0.0, 0.0));
+    }
+    domain.set_pressure(&Coords(2, 3, 3), &[1.0, 1.0]);
+    domain.simulate();
+
+    // first channel moved a whole cell without spreading
+    assert_eq!(domain.pressure(&Coords(2, 3, 3))[0], 0.0);
+    assert_eq!(domain.pressure(&Coords(3, 3, 3))[0], 1.0);
+    // second one spread around in place
+    let [_, centre] = domain.pressure(&Coords(2, 3, 3));
+    let [_, side] = domain.pressure(&Coords(2, 4, 3));
+    assert!(centre < 1.0 && side > 0.0);
+    assert_eq!(domain.pressure(&Coords(3, 3, 3))[1], side);
+}
@@ -0,0 +1,27 @@
+#![allow(dead_code)]
+
+use fluid_simulation::{Domain, DomainProperties, Grid, GridDomain};
+
+/// Domain where nothing moves values on its own: diffusion, decay, pressure
+/// acceleration, vorticity and advection are all off.
+pub fn quiet<const P: usize, const X: usize, const Y: usize, const Z: usize>() -> Domain<P, X, Y, Z>
+{
+    let mut domain: Domain<P, X, Y, Z> = Default::default();
+    silence(&mut domain.prop);
+    domain
+}
+
+/// Publishes values without letting the solver move them.
+pub fn publish<const P_SIZE: usize, G: Grid>(domain: &mut GridDomain<P_SIZE, G>) {
+    silence(&mut domain.prop);
+    domain.simulate();
+}
+
+fn silence(prop: &mut DomainProperties) {
+    prop.diffusion_steps = 0;
+    prop.velocity_decay = None;
+    prop.pressure_acceleration = None;
+    prop.vorticity = None;
+    prop.velocity_props.advection = 0.0;
+    prop.pressure_props.advection = 0.0;
+}
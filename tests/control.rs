@@ -0,0 +1,191 @@
+use fluid_simulation::{
+    Actuator, Controller, Coords, Domain, DomainError, Emitter, Measure, Pid, Shape, Sink,
+};
+
+type Room = Domain<1, 6, 6, 6>;
+
+fn room() -> Room {
+    let mut domain: Room = Default::default();
+    domain.prop.step_delta_time = 0.1;
+    domain.prop.vorticity = None;
+    domain.prop.pressure_acceleration = None;
+    domain
+}
+
+fn invalid(property: &str) -> impl Fn(DomainError) -> bool + '_ {
+    move |e| matches!(e, DomainError::InvalidProperty { property: p, .. } if p == property)
+}
+
+fn whole() -> Shape {
+    Shape::Box {
+        from: Coords(0, 0, 0),
+        to: Coords(6, 6, 6),
+    }
+}
+
+#[test]
+fn holds_smoke_density() {
+    let mut domain = room();
+    let emitter = domain
+        .add_emitter(Emitter {
+            shape: Shape::Point(Coords(3, 3, 3)),
+            pressure: Some((0, 0.0)),
+            velocity: None,
+        })
+        .unwrap();
+    domain
+        .add_sink(Sink {
+            shape: whole(),
+            channel: 0,
+            rate: 0.2,
+        })
+        .unwrap();
+    let measure = Measure::Mass {
+        channel: 0,
+        shape: whole(),
+    };
+    domain
+        .add_controller(Controller {
+            measure: measure.clone(),
+            actuator: Actuator::EmitterPressure(emitter),
+            setpoint: 50.0,
+            pid: Pid::new(0.2, 0.5, 0.0),
+            limits: (0.0, 1000.0),
+        })
+        .unwrap();
+    for _ in 0..150 {
+        domain.simulate();
+    }
+    let mass = domain.measure(&measure);
+    assert!((mass - 50.0).abs() < 1.0, "{}", mass);
+}
+
+#[test]
+fn keeps_inlet_speed() {
+    let mut domain = room();
+    domain.prop.velocity_decay = Some(0.5);
+    let inlet = Shape::Box {
+        from: Coords(0, 0, 0),
+        to: Coords(1, 6, 6),
+    };
+    let fan = domain
+        .add_emitter(Emitter {
+            shape: inlet.clone(),
+            pressure: None,
+            velocity: None,
+        })
+        .unwrap();
+    let measure = Measure::Velocity {
+        axis: 0,
+        shape: inlet,
+    };
+    let handle = domain
+        .add_controller(Controller {
+            measure: measure.clone(),
+            actuator: Actuator::EmitterVelocity {
+                handle: fan,
+                axis: 0,
+            },
+            setpoint: 1.0,
+            pid: Pid::new(0.2, 0.5, 0.0),
+            limits: (-10.0, 10.0),
+        })
+        .unwrap();
+    for _ in 0..100 {
+        domain.simulate();
+    }
+    let speed = domain.measure(&measure);
+    assert!((speed - 1.0).abs() < 0.05, "{}", speed);
+
+    domain.controller_mut(handle).unwrap().setpoint = 0.5;
+    for _ in 0..100 {
+        domain.simulate();
+    }
+    let speed = domain.measure(&measure);
+    assert!((speed - 0.5).abs() < 0.05, "{}", speed);
+}
+
+#[test]
+fn drives_property_within_limits() {
+    let mut domain = room();
+    domain.prop.velocity_decay = None;
+    let cell = Coords(2, 2, 2);
+    let handle = domain
+        .add_controller(Controller {
+            measure: Measure::Pressure { channel: 0, cell },
+            actuator: Actuator::Property(|p| p.vorticity.get_or_insert(0.0)),
+            setpoint: 1.0,
+            pid: Pid::new(1.0, 0.0, 0.0),
+            limits: (0.0, 0.25),
+        })
+        .unwrap();
+    // pressure stays at zero, the property rises until the limit
+    domain.simulate();
+    assert_eq!(domain.prop.vorticity, Some(0.25));
+
+    assert!(domain.remove_controller(handle));
+    assert!(!domain.remove_controller(handle));
+    assert!(domain.controller_mut(handle).is_none());
+    domain.prop.vorticity = Some(0.1);
+    domain.simulate();
+    assert_eq!(domain.prop.vorticity, Some(0.1));
+}
+
+#[test]
+fn rejects_invalid_controllers() {
+    let mut domain = room();
+    let controller = |measure, limits| Controller {
+        measure,
+        actuator: Actuator::Property(|p| &mut p.step_delta_time),
+        setpoint: 0.0,
+        pid: Pid::new(1.0, 0.0, 0.0),
+        limits,
+    };
+    let cell = Coords(2, 2, 2);
+
+    let pressure = Measure::Pressure { channel: 0, cell };
+    for limits in [(1.0, 0.0), (f32::NAN, 1.0), (0.0, f32::NAN)] {
+        let result = domain.add_controller(controller(pressure.clone(), limits));
+        assert!(result.is_err_and(invalid("limits")), "{:?}", limits);
+    }
+    let missing = Measure::Pressure { channel: 1, cell };
+    let result = domain.add_controller(controller(missing, (0.0, 1.0)));
+    assert!(result.is_err_and(invalid("measure.channel")));
+    let missing = Measure::Velocity {
+        axis: 3,
+        shape: whole(),
+    };
+    let result = domain.add_controller(controller(missing, (0.0, 1.0)));
+    assert!(result.is_err_and(invalid("measure.axis")));
+
+    let size = Coords(6, 6, 6);
+    let outside = Measure::Pressure {
+        channel: 0,
+        cell: Coords(0, 6, 0),
+    };
+    assert_eq!(
+        domain.add_controller(controller(outside, (0.0, 1.0))),
+        Err(DomainError::OutOfBounds {
+            coords: Coords(0, 6, 0),
+            size
+        })
+    );
+    let outside = Measure::Mass {
+        channel: 0,
+        shape: Shape::Box {
+            from: Coords(0, 0, 0),
+            to: Coords(7, 6, 6),
+        },
+    };
+    assert_eq!(
+        domain.add_controller(controller(outside, (0.0, 1.0))),
+        Err(DomainError::OutOfBounds {
+            coords: Coords(7, 6, 6),
+            size
+        })
+    );
+
+    // nothing was attached, the timestep is left alone
+    domain.simulate();
+    assert_eq!(domain.prop.step_delta_time, 0.1);
+}
@@ -0,0 +1,65 @@
+use fluid_simulation::{iterator, Boundaries, Boundary, ChannelStats, Coords, Domain, Sized3D};
+
+mod common;
+
+#[test]
+fn diagnostics_summarize_fields() {
+    let mut domain: Domain<2, 4, 4, 4> = Default::default();
+    domain.set_pressure(&Coords(0, 0, 0), &[6.0, -1.0]);
+    domain.set_pressure(&Coords(3, 2, 1), &[2.0, 0.0]);
+    domain.set_velocity(&Coords(1, 1, 1), (0.0, 3.0, 4.0));
+    common::publish(&mut domain);
+
+    let d = domain.diagnostics();
+    assert_eq!(
+        d.pressure[0],
+        ChannelStats {
+            total: 8.0,
+            min: 0.0,
+            max: 6.0,
+            mean: 0.125,
+        }
+    );
+    assert_eq!(d.pressure[1].min, -1.0);
+    assert_eq!(d.max_velocity, 5.0);
+    assert_eq!(d.kinetic_energy, 12.5 / 64.0);
+    assert!(d.divergence > 0.0);
+    assert!(d.enstrophy > 0.0);
+    assert_eq!((d.nan_cells, d.infinite_cells), (0, 0));
+}
+
+#[test]
+fn diagnostics_of_uniform_flow() {
+    let mut domain: Domain<2, 4, 4, 4> = Default::default();
+    domain.prop.boundaries = Boundaries::all(Boundary::Periodic);
+    for c in iterator::iterate(domain.size()) {
+        domain.set_velocity(&c, (1.0, -2.0, 2.0));
+    }
+    common::publish(&mut domain);
+
+    let d = domain.diagnostics();
+    assert_eq!(d.max_velocity, 3.0);
+    assert_eq!(d.kinetic_energy, 4.5);
+    assert_eq!(d.divergence, 0.0);
+    assert_eq!(d.enstrophy, 0.0);
+}
+
+#[test]
+fn diagnostics_count_non_finite_cells() {
+    let mut domain: Domain<2, 4, 4, 4> = Default::default();
+    domain.set_pressure(&Coords(1, 0, 0), &[f32::NAN, f32::INFINITY]);
+    domain.set_pressure(&Coords(2, 0, 0), &[1.0, f32::NEG_INFINITY]);
+    domain.set_pressure(&Coords(3, 0, 0), &[3.0, 0.0]);
+    domain.set_velocity(&Coords(0, 3, 0), (f32::INFINITY, 0.0, 0.0));
+    // zero weights of advection would still smear NaN around
+    domain.prop.step_delta_time = 0.0;
+    common::publish(&mut domain);
+
+    let d = domain.diagnostics();
+    assert_eq!(d.nan_cells, 1);
+    assert_eq!(d.infinite_cells, 2);
+    assert_eq!(d.pressure[0].total, 4.0);
+    assert_eq!(d.pressure[0].mean, 4.0 / 63.0);
+    assert_eq!(d.pressure[1].total, 0.0);
+    assert_eq!(d.max_velocity, 0.0);
+}
@@ -0,0 +1,113 @@
+use fluid_simulation::{iterator, Coords, Domain, DomainError, Emitter, Shape, Sink, Sized3D};
+
+fn total(domain: &Domain<2, 8, 8, 8>, channel: usize) -> f32 {
+    iterator::iterate(domain.size()).fold(0.0, |a, c| a + domain.pressure(&c)[channel])
+}
+
+#[test]
+fn emitter_adds_pressure_at_rate() {
+    let mut domain: Domain<2, 8, 8, 8> = Default::default();
+    let handle = domain
+        .add_emitter(Emitter {
+            shape: Shape::Box {
+                from: Coords(2, 2, 2),
+                to: Coords(4, 4, 3),
+            },
+            pressure: Some((1, 5.0)),
+            velocity: None,
+        })
+        .unwrap();
+    for _ in 0..10 {
+        domain.simulate();
+    }
+    // four cells, 10 steps of 0.1
+    assert!(
+        (total(&domain, 1) - 20.0).abs() < 1e-3,
+        "{}",
+        total(&domain, 1)
+    );
+    assert_eq!(total(&domain, 0), 0.0);
+
+    assert!(domain.remove_emitter(handle));
+    assert!(!domain.remove_emitter(handle));
+    for _ in 0..10 {
+        domain.simulate();
+    }
+    assert!(
+        (total(&domain, 1) - 20.0).abs() < 1e-3,
+        "{}",
+        total(&domain, 1)
+    );
+}
+
+#[test]
+fn emitter_pushes_velocity() {
+    let mut domain: Domain<2, 8, 8, 8> = Default::default();
+    domain.prop.velocity_decay = None;
+    domain
+        .add_emitter(Emitter {
+            shape: Shape::Sphere {
+                center: [4.0, 4.0, 4.0],
+                radius: 1.0,
+            },
+            pressure: None,
+            velocity: Some([0.0, 0.0, 3.0]),
+        })
+        .unwrap();
+    domain.simulate();
+    let (_, _, vz) = domain.velocity(&Coords(4, 4, 4));
+    assert!(vz > 0.0);
+    let (_, _, vz) = domain.velocity(&Coords(0, 0, 0));
+    assert_eq!(vz, 0.0);
+}
+
+#[test]
+fn sink_drains_pressure() {
+    let mut domain: Domain<2, 8, 8, 8> = Default::default();
+    for c in iterator::iterate(domain.size()) {
+        domain.set_pressure(&c, &[1.0, 1.0]);
+    }
+    domain
+        .add_sink(Sink {
+            shape: Shape::Point(Coords(0, 0, 0)),
+            channel: 0,
+            rate: 2.0,
+        })
+        .unwrap();
+    for _ in 0..50 {
+        domain.simulate();
+    }
+    assert!(total(&domain, 0) < 511.0, "{}", total(&domain, 0));
+    assert!(
+        (total(&domain, 1) - 512.0).abs() < 1e-2,
+        "{}",
+        total(&domain, 1)
+    );
+}
+
+#[test]
+fn missing_channel_is_rejected() {
+    let mut domain: Domain<2, 8, 8, 8> = Default::default();
+    let emitter = domain.add_emitter(Emitter {
+        shape: Shape::Point(Coords(0, 0, 0)),
+        pressure: Some((2, 1.0)),
+        velocity: None,
+    });
+    assert!(matches!(
+        emitter,
+        Err(DomainError::InvalidProperty { property, .. }) if property == "pressure"
+    ));
+    let sink = domain.add_sink(Sink {
+        shape: Shape::Point(Coords(0, 0, 0)),
+        channel: 2,
+        rate: 1.0,
+    });
+    assert!(matches!(
+        sink,
+        Err(DomainError::InvalidProperty { property, .. }) if property == "channel"
+    ));
+    // nothing was attached
+    domain.set_pressure(&Coords(0, 0, 0), &[1.0, 1.0]);
+    domain.simulate();
+    assert!((total(&domain, 1) - 1.0).abs() < 1e-6);
+}
@@ -0,0 +1,156 @@
+use fluid_simulation::{
+    Buoyancy, ChannelProperties, Coords, Domain, DomainError, DomainProperties, DynDomain, Pass,
+    Recovery,
+};
+
+fn invalid(property: &str) -> impl Fn(DomainError) -> bool + '_ {
+    move |e| matches!(e, DomainError::InvalidProperty { property: p, .. } if p == property)
+}
+
+#[test]
+fn out_of_range_cells_are_errors() {
+    let mut domain = DynDomain::<2>::new(Coords(3, 4, 5), Default::default());
+    let size = Coords(3, 4, 5);
+    for c in [Coords(3, 0, 0), Coords(0, 4, 0), Coords(0, 0, 5)] {
+        let error = DomainError::OutOfBounds { coords: c, size };
+        assert_eq!(domain.try_pressure(&c), Err(error.clone()));
+        assert_eq!(domain.try_velocity(&c), Err(error.clone()));
+        assert_eq!(domain.try_set_pressure(&c, &[1.0, 2.0]), Err(error.clone()));
+        assert_eq!(domain.try_set_velocity(&c, (1.0, 0.0, 0.0)), Err(error));
+    }
+
+    let c = Coords(2, 3, 4);
+    domain.try_set_pressure(&c, &[1.0, 2.0]).unwrap();
+    domain.try_set_velocity(&c, (1.0, 2.0, 3.0)).unwrap();
+    assert_eq!(domain.try_pressure(&c), Ok(domain.pressure(&c)));
+    assert_eq!(domain.try_velocity(&c), Ok(domain.velocity(&c)));
+}
+
+#[test]
+fn default_properties_are_valid() {
+    assert_eq!(DomainProperties::default().validate(), Ok(()));
+    let mut domain: Domain<1, 4, 4, 4> = Default::default();
+    assert_eq!(domain.try_simulate(), Ok(()));
+}
+
+#[test]
+fn invalid_properties_are_reported_before_running() {
+    let mut domain: Domain<1, 4, 4, 4> = Default::default();
+    domain.set_pressure(&Coords(1, 1, 1), &[1.0]);
+
+    domain.prop.velocity_props.diffusion = -0.1;
+    assert!(domain
+        .try_simulate()
+        .is_err_and(invalid("velocity_props.diffusion")));
+    domain.prop.velocity_props.diffusion = 0.1;
+
+    domain.prop.pressure_channels = vec![ChannelProperties {
+        advection: 0.1,
+        diffusion: f32::NAN,
+        pressure_weight: 1.0,
+        decay: None,
+    }];
+    assert!(domain
+        .try_simulate()
+        .is_err_and(invalid("pressure_channels[0].diffusion")));
+    domain.prop.pressure_channels.clear();
+
+    domain.prop.diffusion_steps = 0;
+    assert!(domain.try_simulate().is_err_and(invalid("diffusion_steps")));
+    domain.prop.diffusion_steps = 1;
+
+    domain.prop.step_delta_time = f32::NAN;
+    assert!(domain.try_simulate().is_err_and(invalid("step_delta_time")));
+    domain.prop.step_delta_time = 1.0;
+
+    domain.prop.buoyancy = Some(Buoyancy {
+        temperature: 0,
+        density: 1,
+        ambient_temperature: 0.0,
+        alpha: 1.0,
+        beta: 1.0,
+        gravity: [0.0, -1.0, 0.0],
+    });
+    assert!(domain
+        .try_simulate()
+        .is_err_and(invalid("buoyancy.density")));
+
+    // nothing ran so far, the set value is not published yet
+    assert_eq!(domain.pressure(&Coords(1, 1, 1)), [0.0]);
+}
+
+#[test]
+fn steps_without_diffusion_need_no_diffusion_steps() {
+    let mut domain: Domain<1, 4, 4, 4> = Default::default();
+    domain.prop.diffusion_steps = 0;
+    domain.prop.velocity_props.diffusion = 0.0;
+    domain.prop.pressure_props.diffusion = 0.0;
+    assert_eq!(domain.prop.validate(), Ok(()));
+}
+
+#[test]
+fn rates_and_step_limits_are_checked() {
+    let check = |change: &dyn Fn(&mut DomainProperties), property| {
+        let mut prop = DomainProperties::default();
+        change(&mut prop);
+        assert!(
+            prop.validate().is_err_and(invalid(property)),
+            "{}",
+            property
+        );
+    };
+    check(
+        &|p| p.velocity_props.advection = -0.1,
+        "velocity_props.advection",
+    );
+    check(&|p| p.velocity_decay = Some(1.5), "velocity_decay");
+    check(&|p| p.velocity_decay = Some(f32::NAN), "velocity_decay");
+    check(
+        &|p| {
+            p.pressure_channels = vec![ChannelProperties {
+                advection: 0.1,
+                diffusion: 0.1,
+                pressure_weight: 1.0,
+                decay: Some(2.0),
+            }]
+        },
+        "pressure_channels[0].decay",
+    );
+    check(&|p| p.target_cfl = 0.0, "target_cfl");
+    check(&|p| p.target_cfl = f32::NAN, "target_cfl");
+    check(&|p| p.max_substeps = 0, "max_substeps");
+    check(&|p| p.projection_tolerance = -1e-3, "projection_tolerance");
+    check(
+        &|p| p.projection_tolerance = f32::NAN,
+        "projection_tolerance",
+    );
+
+    let prop = DomainProperties {
+        velocity_decay: Some(1.0),
+        projection_tolerance: 0.0,
+        ..Default::default()
+    };
+    assert_eq!(prop.validate(), Ok(()));
+}
+
+#[test]
+fn clamp_limit_must_be_finite_and_not_negative() {
+    let mut domain: Domain<1, 4, 4, 4> = Default::default();
+    for limit in [-1.0, f32::NAN, f32::INFINITY] {
+        domain.prop.validation = Some(Recovery::Clamp(limit));
+        assert!(domain.try_simulate().is_err_and(invalid("validation")));
+    }
+    domain.prop.validation = Some(Recovery::Clamp(0.0));
+    assert_eq!(domain.try_simulate(), Ok(()));
+}
+
+#[test]
+fn non_finite_fields_are_wrapped() {
+    let mut domain: Domain<1, 4, 4, 4> = Default::default();
+    domain.prop.validation = Some(Recovery::Stop);
+    domain.set_pressure(&Coords(0, 0, 0), &[f32::INFINITY]);
+    match domain.try_simulate() {
+        Err(DomainError::NonFinite(e)) => assert_eq!(e.pass, Pass::Input),
+        other => panic!("{:?}", other),
+    }
+}
@@ -0,0 +1,178 @@
+use fluid_simulation::{iterator, Coords, DomainError, FlowFlags, Probe, Shape, Sized3D};
+
+mod common;
+
+fn doorway(x: usize) -> Shape {
+    Shape::Box {
+        from: Coords(x, 0, 0),
+        to: Coords(x + 1, 2, 2),
+    }
+}
+
+#[test]
+fn records_points_and_averages() {
+    let mut domain = common::quiet::<2, 4, 4, 4>();
+    domain.prop.step_delta_time = 0.5;
+    let room = Shape::Box {
+        from: Coords(0, 0, 0),
+        to: Coords(2, 1, 1),
+    };
+    domain
+        .add_probe("cell", Probe::Point(Coords(1, 0, 0)), 3)
+        .unwrap();
+    domain.add_probe("room", Probe::Average(room), 3).unwrap();
+    domain.set_pressure(&Coords(0, 0, 0), &[4.0, 1.0]);
+    domain.set_pressure(&Coords(1, 0, 0), &[2.0, 3.0]);
+    domain.set_velocity(&Coords(1, 0, 0), (1.0, 0.0, -1.0));
+    for _ in 0..5 {
+        domain.simulate();
+    }
+
+    let cell = domain.probe("cell").unwrap();
+    assert_eq!(cell.len(), 3);
+    let times: Vec<_> = cell.readings().map(|r| r.time).collect();
+    assert_eq!(times, [1.5, 2.0, 2.5]);
+    let latest = cell.latest().unwrap();
+    assert_eq!(latest.pressure, [2.0, 3.0]);
+    assert_eq!(latest.velocity, [1.0, 0.0, -1.0]);
+    let room = domain.probe("room").unwrap().latest().unwrap();
+    assert_eq!(room.pressure, [3.0, 2.0]);
+    assert_eq!(room.velocity, [0.5, 0.0, -0.5]);
+
+    // solid cells are left out of averages
+    domain.set_solid(&Coords(0, 0, 0), true);
+    domain.simulate();
+    let room = domain.probe("room").unwrap().latest().unwrap();
+    assert_eq!(room.pressure, [2.0, 3.0]);
+}
+
+#[test]
+fn flux_integrates_velocity_across_faces() {
+    let mut domain = common::quiet::<2, 4, 4, 4>();
+    domain.prop.step_delta_time = 0.5;
+    domain.prop.cell_size = 0.5;
+    for c in iterator::iterate(domain.size()) {
+        domain.set_velocity(&c, (1.0, 0.0, 0.0));
+        domain.set_pressure(&c, &[2.0, 0.0]);
+    }
+    let forw = FlowFlags::X_FORW;
+    domain
+        .add_probe(
+            "out",
+            Probe::Flux {
+                shape: doorway(1),
+                face: forw,
+            },
+            8,
+        )
+        .unwrap();
+    let back = FlowFlags::X_BACK;
+    domain
+        .add_probe(
+            "in",
+            Probe::Flux {
+                shape: doorway(2),
+                face: back,
+            },
+            8,
+        )
+        .unwrap();
+    domain
+        .add_probe(
+            "wall",
+            Probe::Flux {
+                shape: doorway(3),
+                face: forw,
+            },
+            8,
+        )
+        .unwrap();
+    domain.simulate();
+
+    let latest = |name| *domain.probe(name).unwrap().latest().unwrap();
+    // four cells of a quarter of unit area at unit speed
+    assert_eq!(latest("out").flow, 1.0);
+    assert_eq!(latest("out").pressure, [2.0, 0.0]);
+    assert_eq!(latest("out").velocity, [0.0; 3]);
+    assert_eq!(latest("in").flow, -1.0);
+    assert_eq!(latest("wall").flow, 0.0);
+
+    domain.set_wall(&Coords(1, 1, 1), &Coords(2, 1, 1), true);
+    domain.simulate();
+    let latest = |name| *domain.probe(name).unwrap().latest().unwrap();
+    assert_eq!(latest("out").flow, 0.75);
+    assert_eq!(latest("in").flow, -0.75);
+}
+
+#[test]
+fn probes_are_named_and_exported() {
+    let mut domain = common::quiet::<2, 4, 4, 4>();
+    domain.prop.step_delta_time = 0.5;
+    domain
+        .add_probe("a", Probe::Point(Coords(0, 0, 0)), 4)
+        .unwrap();
+    domain
+        .add_probe("b", Probe::Point(Coords(1, 0, 0)), 4)
+        .unwrap();
+    domain.set_pressure(&Coords(0, 0, 0), &[1.0, 2.0]);
+    domain.simulate();
+    domain.simulate();
+
+    let mut csv = Vec::new();
+    domain.probe("a").unwrap().write_csv(&mut csv).unwrap();
+    assert_eq!(
+        String::from_utf8(csv).unwrap(),
+        "time,pressure_0,pressure_1,velocity_x,velocity_y,velocity_z,flow\n\
+         0.5,1,2,0,0,0,0\n\
+         1,1,2,0,0,0,0\n"
+    );
+
+    // replacing a probe starts a new series
+    domain
+        .add_probe("a", Probe::Point(Coords(2, 0, 0)), 4)
+        .unwrap();
+    assert!(domain.probe("a").unwrap().is_empty());
+    let names: Vec<_> = domain.probes().map(|(n, _)| n).collect();
+    assert_eq!(names, ["a", "b"]);
+    assert!(domain.remove_probe("a"));
+    assert!(!domain.remove_probe("a"));
+    assert!(domain.probe("a").is_none());
+    assert_eq!(domain.probe("b").unwrap().len(), 2);
+}
+
+#[test]
+fn rejects_probes_outside_or_without_face() {
+    let mut domain = common::quiet::<2, 4, 4, 4>();
+    domain.prop.step_delta_time = 0.5;
+    let size = Coords(4, 4, 4);
+    assert_eq!(
+        domain.add_probe("a", Probe::Point(Coords(4, 0, 0)), 4),
+        Err(DomainError::OutOfBounds {
+            coords: Coords(4, 0, 0),
+            size
+        })
+    );
+    let beyond = Shape::Box {
+        from: Coords(0, 0, 0),
+        to: Coords(1, 5, 1),
+    };
+    assert_eq!(
+        domain.add_probe("a", Probe::Average(beyond.clone()), 4),
+        Err(DomainError::OutOfBounds {
+            coords: Coords(1, 5, 1),
+            size
+        })
+    );
+    let flux = |shape, face| Probe::Flux { shape, face };
+    let result = domain.add_probe("a", flux(beyond, FlowFlags::X_FORW), 4);
+    assert!(matches!(result, Err(DomainError::OutOfBounds { .. })));
+    for face in [FlowFlags::empty(), FlowFlags::X_FORW | FlowFlags::Y_FORW] {
+        let result = domain.add_probe("a", flux(doorway(1), face), 4);
+        assert!(
+            matches!(result, Err(DomainError::InvalidProperty { ref property, .. }) if property == "face"),
+            "{:?}",
+            face
+        );
+    }
+    assert_eq!(domain.probes().count(), 0);
+}
@@ -0,0 +1,104 @@
+use fluid_simulation::{
+    Boundaries, Boundary, Domain, DomainError, DomainProperties, PropertyChange, VorticityMode,
+};
+
+fn tuned() -> DomainProperties {
+    let mut prop = DomainProperties {
+        velocity_decay: Some(0.4),
+        diffusion_steps: 5,
+        vorticity_mode: VorticityMode::Curl,
+        step_delta_time: 0.5,
+        ..Default::default()
+    };
+    prop.velocity_props.diffusion = 0.3;
+    prop
+}
+
+#[test]
+fn reports_changes() {
+    let mut domain: Domain<1, 4, 4, 4> = Default::default();
+    domain.prop.velocity_decay = None;
+    domain.prop.velocity_props.diffusion = 0.1;
+    let changes = domain.update_properties(tuned(), 0).unwrap();
+    let properties: Vec<_> = changes.iter().map(|c| c.property).collect();
+    assert_eq!(
+        properties,
+        [
+            "velocity_props.diffusion",
+            "diffusion_steps",
+            "step_delta_time",
+            "velocity_decay",
+            "vorticity_mode"
+        ]
+    );
+    let change = PropertyChange {
+        property: "velocity_decay",
+        old: "None".into(),
+        new: "Some(0.4)".into(),
+    };
+    assert_eq!(changes[3], change);
+    assert_eq!(change.to_string(), "velocity_decay: None -> Some(0.4)");
+    assert_eq!(domain.prop, tuned());
+    assert!(domain.update_properties(tuned(), 0).unwrap().is_empty());
+}
+
+#[test]
+fn numeric_properties_are_blended() {
+    let mut domain: Domain<1, 4, 4, 4> = Default::default();
+    domain.prop.velocity_decay = None;
+    domain.prop.velocity_props.diffusion = 0.1;
+    let start = domain.prop.clone();
+    let mut target = tuned();
+    target.boundaries = Boundaries::all(Boundary::Outflow);
+    domain.update_properties(target.clone(), 4).unwrap();
+
+    // nothing but the timestep moves before the next step
+    assert_eq!(domain.prop.step_delta_time, 0.5);
+    assert_eq!(domain.prop.velocity_decay, start.velocity_decay);
+    assert!(domain.is_blending());
+
+    let mut decay = Vec::new();
+    let mut steps = Vec::new();
+    for _ in 0..3 {
+        domain.simulate();
+        decay.push(domain.prop.velocity_decay.unwrap());
+        steps.push(domain.prop.diffusion_steps);
+        assert_eq!(domain.prop.boundaries, target.boundaries);
+        assert_eq!(domain.prop.vorticity_mode, VorticityMode::Curl);
+    }
+    for (d, expected) in decay.iter().zip([0.1, 0.2, 0.3]) {
+        assert!((d - expected).abs() < 1e-6, "{:?}", decay);
+    }
+    assert_eq!(steps, [2, 3, 4]);
+    assert!((domain.prop.velocity_props.diffusion - 0.25).abs() < 1e-6);
+
+    domain.simulate();
+    assert!(!domain.is_blending());
+    assert_eq!(domain.prop, target);
+}
+
+#[test]
+fn blend_keeps_substep_timestep() {
+    let mut domain: Domain<1, 4, 4, 4> = Default::default();
+    domain.update_properties(tuned(), 3).unwrap();
+    // still flow takes a single substep per call
+    for _ in 0..3 {
+        assert_eq!(domain.simulate_for(2.0), Ok(1));
+        assert_eq!(domain.prop.step_delta_time, 0.5);
+    }
+    assert!(!domain.is_blending());
+    assert_eq!(domain.prop, tuned());
+}
+
+#[test]
+fn invalid_properties_are_rejected() {
+    let mut domain: Domain<1, 4, 4, 4> = Default::default();
+    let prop = DomainProperties {
+        step_delta_time: f32::NAN,
+        ..tuned()
+    };
+    let error = domain.update_properties(prop, 4);
+    assert!(matches!(error, Err(DomainError::InvalidProperty { .. })));
+    assert_eq!(domain.prop, DomainProperties::default());
+    assert!(!domain.is_blending());
+}
@@ -0,0 +1,58 @@
+use fluid_simulation::{Boundaries, Boundary, Coords, Domain};
+
+mod common;
+
+#[test]
+fn sample_interpolates_between_cells() {
+    let mut domain: Domain<1, 4, 4, 4> = Default::default();
+    domain.set_pressure(&Coords(1, 1, 1), &[8.0]);
+    domain.set_velocity(&Coords(2, 1, 1), (4.0, 0.0, -4.0));
+    common::publish(&mut domain);
+
+    assert_eq!(domain.sample_pressure([1.0, 1.0, 1.0]), [8.0]);
+    assert_eq!(domain.sample_pressure([1.5, 1.0, 1.0]), [4.0]);
+    assert_eq!(domain.sample_pressure([1.5, 1.5, 1.5]), [1.0]);
+    assert_eq!(domain.sample_velocity([1.75, 1.0, 1.0]), (3.0, 0.0, -3.0));
+}
+
+#[test]
+fn sample_clamps_to_walls() {
+    let mut domain: Domain<1, 4, 4, 4> = Default::default();
+    domain.set_pressure(&Coords(0, 0, 0), &[2.0]);
+    common::publish(&mut domain);
+
+    assert_eq!(domain.sample_pressure([-3.0, -0.5, 0.0]), [2.0]);
+    assert_eq!(domain.sample_pressure([0.0, 0.0, 9.0]), [0.0]);
+}
+
+#[test]
+fn sample_does_not_leak_through_walls() {
+    let mut domain: Domain<1, 4, 4, 4> = Default::default();
+    domain.set_wall(&Coords(1, 1, 1), &Coords(2, 1, 1), true);
+    domain.set_pressure(&Coords(2, 1, 1), &[8.0]);
+    common::publish(&mut domain);
+
+    assert_eq!(domain.sample_pressure([1.4, 1.0, 1.0]), [0.0]);
+    assert_eq!(domain.sample_pressure([1.6, 1.0, 1.0]), [8.0]);
+    // around the wall end the value still spreads
+    assert!(domain.sample_pressure([1.5, 1.5, 1.0])[0] > 0.0);
+}
+
+#[test]
+fn sample_far_outside_open_faces() {
+    let mut domain: Domain<1, 4, 4, 4> = Default::default();
+    domain.prop.boundaries = Boundaries::all(Boundary::Outflow);
+    domain.prop.boundaries.x_forw = Boundary::Inflow {
+        pressure: vec![3.0],
+        velocity: [0.0; 3],
+    };
+    domain.prop.boundaries.z_forw = Boundary::Periodic;
+    domain.prop.boundaries.z_back = Boundary::Periodic;
+    domain.set_pressure(&Coords(1, 1, 1), &[8.0]);
+    common::publish(&mut domain);
+
+    assert_eq!(domain.sample_pressure([1e30, 1.0, 1.0]), [3.0]);
+    assert_eq!(domain.sample_pressure([1.0, -1e30, 1.0]), [0.0]);
+    assert_eq!(domain.sample_velocity([-1e30, 1e30, 1.0]), (0.0, 0.0, 0.0));
+    assert_eq!(domain.sample_pressure([1.0, 1.0, 4001.0]), [8.0]);
+}
@@ -0,0 +1,97 @@
+#![cfg(feature = "serde")]
+
+use fluid_simulation::{Coords, DomainError, DomainProperties, FlowFlags, Scene, Sized3D};
+
+const SCENE: &str = r#"
+size = [6, 5, 4]
+
+[properties]
+velocity_decay = 0.2
+vorticity = 0.3
+diffusion_steps = 2
+velocity_props = { advection = 0.5, diffusion = 0.25 }
+
+[[obstacles]]
+shape = { Box = { from = [0, 0, 0], to = [2, 1, 4] } }
+
+[[obstacles]]
+shape = { Point = [4, 2, 2] }
+faces = "X_FORW | Y_BACK"
+
+[[emitters]]
+shape = { Point = [3, 3, 3] }
+pressure = [1, 5.0]
+
+[[pressure]]
+shape = { Box = { from = [3, 3, 0], to = [5, 5, 1] } }
+value = [1.0, 2.0]
+
+[[velocity]]
+shape = { Point = [5, 4, 3] }
+value = [0.0, 1.0, 0.0]
+"#;
+
+#[test]
+fn toml_scene_builds_domain() {
+    let scene: Scene = toml::from_str(SCENE).unwrap();
+    let domain = scene.build::<2>().unwrap();
+    assert_eq!(domain.size(), Coords(6, 5, 4));
+
+    let defaults = DomainProperties::default();
+    assert_eq!(domain.prop.velocity_decay, Some(0.2));
+    assert_eq!(domain.prop.vorticity, Some(0.3));
+    assert_eq!(domain.prop.diffusion_steps, 2);
+    assert_eq!(domain.prop.velocity_props.advection, 0.5);
+    assert_eq!(domain.prop.velocity_props.diffusion, 0.25);
+    assert_eq!(domain.prop.pressure_props, defaults.pressure_props);
+    assert_eq!(domain.prop.cell_size, defaults.cell_size);
+    assert_eq!(domain.config(), Some(&domain.prop));
+
+    assert!(domain.is_solid(&Coords(1, 0, 3)));
+    assert!(!domain.is_solid(&Coords(2, 0, 3)));
+    let faces = FlowFlags::X_FORW | FlowFlags::Y_BACK;
+    assert_eq!(domain.blocked_faces(&Coords(4, 2, 2)), faces);
+    assert_eq!(domain.blocked_faces(&Coords(5, 2, 2)), FlowFlags::X_BACK);
+    assert_eq!(domain.blocked_faces(&Coords(4, 1, 2)), FlowFlags::Y_FORW);
+
+    assert_eq!(domain.pressure(&Coords(4, 4, 0)), [1.0, 2.0]);
+    assert_eq!(domain.pressure(&Coords(5, 4, 0)), [0.0, 0.0]);
+    assert_eq!(domain.velocity(&Coords(5, 4, 3)), (0.0, 1.0, 0.0));
+}
+
+#[test]
+fn json_round_trip() {
+    let scene: Scene = toml::from_str(SCENE).unwrap();
+    let json = serde_json::to_string(&scene).unwrap();
+    assert_eq!(serde_json::from_str::<Scene>(&json).unwrap(), scene);
+
+    let empty: Scene = serde_json::from_str(r#"{"size": [2, 2, 2]}"#).unwrap();
+    let domain = empty.build::<1>().unwrap();
+    assert_eq!(domain.prop, DomainProperties::default());
+}
+
+#[test]
+fn mismatched_scenes_are_errors() {
+    let scene: Scene = toml::from_str(SCENE).unwrap();
+    assert!(matches!(
+        scene.build::<3>(),
+        Err(DomainError::InvalidProperty { property, .. }) if property == "pressure[0].value"
+    ));
+
+    let mut scene = scene;
+    scene.pressure.clear();
+    assert!(matches!(
+        scene.build::<1>(),
+        Err(DomainError::InvalidProperty { property, .. }) if property == "emitters"
+    ));
+
+    scene.emitters.clear();
+    scene.size = Coords(5, 5, 4);
+    assert!(matches!(
+        scene.build::<1>(),
+        Err(DomainError::OutOfBounds {
+            coords: Coords(5, 4, 3),
+            ..
+        })
+    ));
+}
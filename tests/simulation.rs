@@ -1,4 +1,6 @@
-use fluid_simulation::{iterator, Coords, Domain, Sized3D};
+use std::hash::{DefaultHasher, Hash, Hasher};
+
+use fluid_simulation::{iterator, Boundary, Coords, Domain, DynDomain, Sized3D};
 
 #[test]
 fn diffusion_stability() {
@@ -16,3 +18,87 @@ fn diffusion_stability() {
         assert!((sum - 32.0).abs() < 0.0001, "iter = {} sum = {}", n, sum);
     }
 }
+
+#[test]
+fn dyn_domain_matches_fixed() {
+    let mut fixed: Domain<1, 6, 5, 4> = Default::default();
+    let mut dynamic = DynDomain::<1>::new(Coords(6, 5, 4), Default::default());
+    assert_eq!(dynamic.size(), fixed.size());
+    for c in [Coords(1, 1, 1), Coords(4, 3, 2)] {
+        fixed.set_pressure(&c, &[16.0]);
+        fixed.set_velocity(&c, (1.0, -0.5, 2.0));
+        dynamic.set_pressure(&c, &[16.0]);
+        dynamic.set_velocity(&c, (1.0, -0.5, 2.0));
+    }
+    for _ in 0..100 {
+        fixed.simulate();
+        dynamic.simulate();
+    }
+    for c in iterator::iterate(fixed.size()) {
+        assert_eq!(fixed.pressure(&c), dynamic.pressure(&c), "at {:?}", c);
+        assert_eq!(fixed.velocity(&c), dynamic.velocity(&c), "at {:?}", c);
+    }
+}
+
+#[test]
+fn parallel_matches_serial() {
+    let run = |threads: usize| {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()
+            .unwrap();
+        let mut domain = DynDomain::<1>::new(Coords(5, 4, 21), Default::default());
+        domain.prop.diffusion_steps = 0;
+        domain.prop.velocity_props.advection = 10.0;
+        domain.prop.pressure_props.advection = 10.0;
+        domain.prop.boundaries.z_forw = Boundary::Periodic;
+        domain.prop.boundaries.z_back = Boundary::Periodic;
+        for z in 0..21 {
+            let c = Coords(z % 5, z % 4, z);
+            domain.set_pressure(&c, &[z as f32]);
+            domain.set_velocity(&c, (0.5, -1.0, z as f32 - 10.0));
+        }
+        pool.install(|| {
+            for _ in 0..20 {
+                domain.simulate();
+            }
+        });
+        iterator::iterate(domain.size())
+            .map(|c| (domain.pressure(&c), domain.velocity(&c)))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(run(1), run(4));
+}
+
+#[test]
+fn hash_independent_of_thread_count() {
+    let run = |threads: usize| {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()
+            .unwrap();
+        let mut domain = DynDomain::<2>::new(Coords(7, 6, 19), Default::default());
+        domain.prop.diffusion_steps = 3;
+        domain.prop.boundaries.x_forw = Boundary::Outflow;
+        domain.fill_sphere([3.0, 3.0, 9.0], 1.5, true);
+        domain.set_pressure(&Coords(1, 1, 1), &[50.0, 10.0]);
+        domain.set_pressure(&Coords(5, 4, 17), &[5.0, 30.0]);
+        domain.set_velocity(&Coords(2, 3, 4), (3.0, 1.0, 8.0));
+        pool.install(|| {
+            for _ in 0..50 {
+                domain.simulate();
+            }
+        });
+        let mut hasher = DefaultHasher::new();
+        for c in iterator::iterate(domain.size()) {
+            domain.pressure(&c).map(f32::to_bits).hash(&mut hasher);
+            let (x, y, z) = domain.velocity(&c);
+            [x, y, z].map(f32::to_bits).hash(&mut hasher);
+        }
+        hasher.finish()
+    };
+    let serial = run(1);
+    for threads in [2, 3, 8] {
+        assert_eq!(serial, run(threads), "threads = {}", threads);
+    }
+}
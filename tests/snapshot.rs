@@ -0,0 +1,104 @@
+#![cfg(feature = "serde")]
+
+use fluid_simulation::{
+    iterator, Boundaries, Boundary, Coords, Domain, DynDomain, Sized3D, SnapshotError,
+    SNAPSHOT_VERSION,
+};
+
+fn busy_domain() -> Domain<2, 6, 5, 4> {
+    let mut domain: Domain<2, 6, 5, 4> = Default::default();
+    domain.prop.vorticity = Some(0.3);
+    domain.prop.boundaries = Boundaries::all(Boundary::Outflow);
+    domain.prop.boundaries.x_back = Boundary::Inflow {
+        pressure: vec![2.0, 1.0],
+        velocity: [0.5, 0.0, 0.0],
+    };
+    domain.fill_box(&Coords(2, 2, 1), &Coords(4, 3, 3), true);
+    domain.set_pressure(&Coords(1, 1, 1), &[10.0, 3.0]);
+    domain.set_velocity(&Coords(4, 4, 2), (1.0, -2.0, 0.5));
+    for _ in 0..10 {
+        domain.simulate();
+    }
+    domain
+}
+
+#[test]
+fn snapshot_round_trip() {
+    let mut original = busy_domain();
+    let mut bytes = Vec::new();
+    original.save_snapshot(&mut bytes).unwrap();
+
+    let mut restored: Domain<2, 6, 5, 4> = Default::default();
+    restored.load_snapshot(bytes.as_slice()).unwrap();
+    assert_eq!(restored.prop.vorticity, Some(0.3));
+    assert_eq!(restored.prop.boundaries, original.prop.boundaries);
+    for _ in 0..10 {
+        original.simulate();
+        restored.simulate();
+    }
+    for c in iterator::iterate(original.size()) {
+        assert_eq!(original.pressure(&c), restored.pressure(&c), "at {:?}", c);
+        assert_eq!(original.velocity(&c), restored.velocity(&c), "at {:?}", c);
+        assert_eq!(original.blocked_faces(&c), restored.blocked_faces(&c));
+    }
+}
+
+#[test]
+fn snapshot_rejects_mismatch() {
+    let mut bytes = Vec::new();
+    busy_domain().save_snapshot(&mut bytes).unwrap();
+
+    let mut channels: Domain<1, 6, 5, 4> = Default::default();
+    assert!(matches!(
+        channels.load_snapshot(bytes.as_slice()),
+        Err(SnapshotError::Channels {
+            expected: 1,
+            found: 2
+        })
+    ));
+    let mut size = DynDomain::<2>::new(Coords(6, 5, 5), Default::default());
+    assert!(matches!(
+        size.load_snapshot(bytes.as_slice()),
+        Err(SnapshotError::Size { .. })
+    ));
+    assert!(matches!(
+        size.load_snapshot(&b"not a snapshot"[..]),
+        Err(SnapshotError::NotSnapshot)
+    ));
+    let mut truncated: Domain<2, 6, 5, 4> = Default::default();
+    assert!(matches!(
+        truncated.load_snapshot(&bytes[..bytes.len() / 2]),
+        Err(SnapshotError::Decode(_))
+    ));
+}
+
+// Any change to these bytes needs a bump of SNAPSHOT_VERSION
+#[test]
+fn snapshot_layout_is_pinned() {
+    let domain: Domain<1, 1, 1, 1> = Default::default();
+    let mut bytes = Vec::new();
+    domain.save_snapshot(&mut bytes).unwrap();
+    assert_eq!(&bytes[..4], b"FLSN");
+    assert_eq!(bytes[4..8], SNAPSHOT_VERSION.to_le_bytes());
+    assert_eq!(SNAPSHOT_VERSION, 8);
+    #[rustfmt::skip]
+    let expected: [u8; 101] = [
+        // header: channels, size
+        1, 1, 1, 1,
+        // properties
+        205, 204, 204, 61, 205, 204, 204, 61, 0, 205, 204, 204, 61, 205, 204, 204, 61, 0, 0, 0, 0,
+        128, 63, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 205, 204, 204, 61, 0, 0, 128, 63, 64, 1,
+        205, 204, 204, 61, 1, 205, 204, 204, 61, 1, 205, 204, 204, 61, 0, 0, 0, 23, 183, 209, 56, 0,
+        0, 0, 0, 0, 0, 0,
+        // fields: velocity x, y, z, pressure, blockage
+        1, 0, 0, 0, 0, 1, 0, 0, 0, 0, 1, 0, 0, 0, 0, 1, 1, 0, 0, 0, 0, 1, 0,
+    ];
+    assert_eq!(bytes[8..], expected);
+
+    let mut restored: Domain<1, 1, 1, 1> = Default::default();
+    bytes[4] = 1;
+    assert!(matches!(
+        restored.load_snapshot(bytes.as_slice()),
+        Err(SnapshotError::Version(1))
+    ));
+}
@@ -0,0 +1,45 @@
+use fluid_simulation::{iterator, Coords, Sized3D};
+
+mod common;
+
+#[test]
+fn cfl_follows_fastest_cell() {
+    let mut domain = common::quiet::<1, 6, 4, 4>();
+    domain.prop.pressure_props.advection = 1.0;
+    domain.set_velocity(&Coords(2, 1, 3), (3.0, -4.0, 0.0));
+    domain.set_velocity(&Coords(4, 0, 0), (1.0, 1.0, 1.0));
+    domain.prop.step_delta_time = 0.0;
+    domain.simulate();
+    domain.prop.step_delta_time = 0.1;
+    domain.prop.cell_size = 0.5;
+    assert_eq!(domain.max_velocity(), 5.0);
+    assert_eq!(domain.cfl(), 1.0);
+}
+
+#[test]
+fn simulate_for_splits_by_cfl() {
+    let mut domain = common::quiet::<1, 6, 4, 4>();
+    domain.prop.pressure_props.advection = 1.0;
+    for c in iterator::iterate(domain.size()) {
+        domain.set_velocity(&c, (10.0, 0.0, 0.0));
+    }
+    domain.prop.step_delta_time = 0.0;
+    domain.simulate();
+
+    domain.prop.step_delta_time = 0.25;
+    assert_eq!(domain.simulate_for(1.0), Ok(10));
+    assert_eq!(domain.prop.step_delta_time, 0.25);
+    domain.prop.target_cfl = 0.5;
+    assert_eq!(domain.simulate_for(0.5), Ok(10));
+
+    domain.prop.max_substeps = 4;
+    assert_eq!(domain.simulate_for(1.0), Ok(4));
+    assert_eq!(domain.simulate_for(0.0), Ok(0));
+}
+
+#[test]
+fn simulate_for_still_flow_takes_one_step() {
+    let mut domain = common::quiet::<1, 6, 4, 4>();
+    domain.prop.pressure_props.advection = 1.0;
+    assert_eq!(domain.simulate_for(3.0), Ok(1));
+}
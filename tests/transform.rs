@@ -0,0 +1,47 @@
+use fluid_simulation::{iterator, Coords, DynDomain, Sized3D};
+
+#[test]
+fn world_coords_round_trip() {
+    let mut domain = DynDomain::<1>::new(Coords(4, 3, 2), Default::default());
+    domain.prop.origin = [10.0, 0.0, -5.0];
+    domain.prop.cell_size = 0.5;
+
+    assert_eq!(domain.coords_to_world(&Coords(2, 1, 0)), [11.0, 0.5, -5.0]);
+    assert_eq!(
+        domain.world_to_coords([11.2, 0.3, -4.8]),
+        Some(Coords(2, 1, 0))
+    );
+    assert_eq!(domain.world_to_cell([11.0, 0.5, -4.0]), [2.0, 1.0, 2.0]);
+    assert_eq!(domain.world_to_coords([9.7, 0.0, -5.0]), None);
+    assert_eq!(domain.world_to_coords([12.0, 0.0, -5.0]), None);
+}
+
+// Velocity gained by the first cell of a pressure ramp rising by one per
+// world unit along x
+fn ramp_push(cells: usize, cell_size: f32) -> f32 {
+    let mut domain = DynDomain::<1>::new(Coords(cells, 1, 1), Default::default());
+    domain.prop.cell_size = cell_size;
+    domain.prop.diffusion_steps = 0;
+    domain.prop.velocity_decay = None;
+    domain.prop.pressure_acceleration = Some(1.0);
+    domain.prop.vorticity = None;
+    for c in iterator::iterate(domain.size()) {
+        let x = domain.coords_to_world(&c)[0];
+        domain.set_pressure(&c, &[x]);
+    }
+    domain.simulate();
+    domain.velocity(&Coords(0, 0, 0)).0
+}
+
+#[test]
+fn pressure_acceleration_independent_of_resolution() {
+    let coarse = ramp_push(8, 1.0);
+    let fine = ramp_push(16, 0.5);
+    assert!(coarse > 0.0, "coarse = {}", coarse);
+    assert!(
+        (coarse - fine).abs() < 1e-4,
+        "coarse = {} fine = {}",
+        coarse,
+        fine
+    );
+}
@@ -0,0 +1,110 @@
+use fluid_simulation::{iterator, Coords, Domain, NonFiniteError, Pass, Recovery, Sized3D};
+
+mod common;
+
+fn all_finite(domain: &Domain<1, 4, 4, 4>) -> bool {
+    iterator::iterate(domain.size()).all(|c| {
+        let (x, y, z) = domain.velocity(&c);
+        [x, y, z, domain.pressure(&c)[0]]
+            .iter()
+            .all(|v| v.is_finite())
+    })
+}
+
+#[test]
+fn reports_first_offending_cell() {
+    let mut domain = common::quiet::<1, 4, 4, 4>();
+    domain.prop.validation = Some(Recovery::Stop);
+    domain.set_pressure(&Coords(1, 2, 3), &[f32::NAN]);
+    domain.set_velocity(&Coords(3, 1, 3), (0.0, f32::INFINITY, 0.0));
+    let error = domain.simulate_checked().unwrap_err();
+    assert_eq!(
+        error,
+        NonFiniteError {
+            pass: Pass::Input,
+            coords: Coords(3, 1, 3),
+        }
+    );
+    assert_eq!(
+        error.to_string(),
+        "Input pass left a non-finite value at (3, 1, 3)"
+    );
+}
+
+#[test]
+fn names_the_failing_pass() {
+    let mut domain = common::quiet::<1, 4, 4, 4>();
+    domain.prop.validation = Some(Recovery::Stop);
+    domain.prop.pressure_acceleration = Some(1e30);
+    domain.set_pressure(&Coords(1, 1, 1), &[1e30]);
+    let error = domain.simulate_checked().unwrap_err();
+    assert_eq!(error.pass, Pass::Forces);
+
+    domain.prop.validation = None;
+    domain.prop.pressure_acceleration = None;
+    assert_eq!(domain.simulate_checked(), Ok(()));
+}
+
+#[test]
+fn clamp_and_zero_repair_fields() {
+    let mut domain = common::quiet::<1, 4, 4, 4>();
+    domain.prop.validation = Some(Recovery::Clamp(100.0));
+    domain.set_pressure(&Coords(0, 0, 0), &[f32::NAN]);
+    domain.set_pressure(&Coords(1, 0, 0), &[f32::INFINITY]);
+    domain.set_velocity(&Coords(1, 0, 0), (-1e20, 5.0, 0.0));
+    domain.set_pressure(&Coords(2, 0, 0), &[1e20]);
+    assert_eq!(domain.simulate_checked().unwrap_err().pass, Pass::Input);
+    assert!(all_finite(&domain));
+    assert_eq!(domain.pressure(&Coords(0, 0, 0)), [0.0]);
+    assert_eq!(domain.pressure(&Coords(1, 0, 0)), [100.0]);
+    assert_eq!(domain.velocity(&Coords(1, 0, 0)), (-100.0, 5.0, 0.0));
+    // finite cells are left alone
+    assert_eq!(domain.pressure(&Coords(2, 0, 0)), [1e20]);
+    assert_eq!(domain.simulate_checked(), Ok(()));
+
+    let mut domain = common::quiet::<1, 4, 4, 4>();
+    domain.prop.validation = Some(Recovery::Zero);
+    domain.set_pressure(&Coords(2, 2, 2), &[3.0]);
+    domain.set_velocity(&Coords(2, 2, 2), (f32::NEG_INFINITY, 1.0, 0.0));
+    domain.set_pressure(&Coords(1, 2, 2), &[3.0]);
+    domain.simulate();
+    assert!(all_finite(&domain));
+    assert_eq!(domain.pressure(&Coords(2, 2, 2)), [0.0]);
+    assert_eq!(domain.velocity(&Coords(2, 2, 2)), (0.0, 0.0, 0.0));
+    assert_eq!(domain.pressure(&Coords(1, 2, 2)), [3.0]);
+}
+
+#[test]
+fn rollback_restores_published_fields() {
+    let mut domain = common::quiet::<1, 4, 4, 4>();
+    domain.prop.validation = Some(Recovery::Rollback);
+    domain.set_pressure(&Coords(1, 1, 1), &[2.0]);
+    domain.simulate();
+
+    domain.set_pressure(&Coords(1, 1, 1), &[7.0]);
+    domain.set_velocity(&Coords(3, 3, 3), (f32::NAN, 0.0, 0.0));
+    assert_eq!(domain.simulate_checked().unwrap_err().pass, Pass::Input);
+    assert!(all_finite(&domain));
+    assert_eq!(domain.pressure(&Coords(1, 1, 1)), [2.0]);
+
+    // the next step starts from the restored fields
+    domain.simulate();
+    assert_eq!(domain.pressure(&Coords(1, 1, 1)), [2.0]);
+    assert!(all_finite(&domain));
+}
+
+#[test]
+fn simulate_for_reports_errors() {
+    let mut domain = common::quiet::<1, 4, 4, 4>();
+    domain.prop.validation = Some(Recovery::Zero);
+    domain.set_pressure(&Coords(1, 1, 1), &[f32::NAN]);
+    let error = domain.simulate_for(1.0).unwrap_err();
+    assert_eq!(error.coords, Coords(1, 1, 1));
+    assert!(all_finite(&domain));
+    assert_eq!(domain.simulate_for(1.0), Ok(1));
+
+    domain.prop.validation = Some(Recovery::Stop);
+    domain.set_velocity(&Coords(2, 1, 1), (f32::INFINITY, 0.0, 0.0));
+    assert_eq!(domain.simulate_for(1.0).unwrap_err().pass, Pass::Input);
+    assert_eq!(domain.prop.step_delta_time, 0.1);
+}
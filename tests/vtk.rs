@@ -0,0 +1,89 @@
+use fluid_simulation::{Coords, Domain, VtkSeries};
+
+fn find(haystack: &[u8], needle: &str) -> usize {
+    haystack
+        .windows(needle.len())
+        .position(|w| w == needle.as_bytes())
+        .unwrap_or_else(|| panic!("{} not found", needle))
+}
+
+#[test]
+fn vtk_holds_every_field() {
+    let mut domain: Domain<2, 4, 3, 2> = Default::default();
+    domain.set_pressure(&Coords(1, 2, 1), &[5.0, 7.0]);
+    domain.set_velocity(&Coords(1, 2, 1), (1.0, 2.0, 3.0));
+    domain.set_solid(&Coords(3, 0, 0), true);
+    domain.prop.diffusion_steps = 0;
+    domain.prop.velocity_decay = None;
+    domain.prop.pressure_acceleration = None;
+    domain.prop.vorticity = None;
+    domain.prop.velocity_props.advection = 0.0;
+    domain.prop.pressure_props.advection = 0.0;
+    domain.simulate();
+
+    let mut out = Vec::new();
+    domain.write_vtk(&mut out).unwrap();
+    let text = String::from_utf8_lossy(&out);
+    assert!(text.starts_with("# vtk DataFile Version 3.0\n"));
+    assert!(text.contains("DIMENSIONS 4 3 2\n"));
+    assert!(text.contains("POINT_DATA 24\n"));
+
+    let cell = 1 + 4 * (2 + 3);
+    let float = |at: usize| f32::from_be_bytes(out[at..at + 4].try_into().unwrap());
+    let table = "LOOKUP_TABLE default\n";
+    let p0 = find(&out, "SCALARS pressure_0 float 1\n") + 27 + table.len();
+    assert_eq!(float(p0 + 4 * cell), 5.0);
+    let p1 = find(&out, "SCALARS pressure_1 float 1\n") + 27 + table.len();
+    assert_eq!(float(p1 + 4 * cell), 7.0);
+    let v = find(&out, "VECTORS velocity float\n") + 23;
+    assert_eq!(float(v + 12 * cell), 1.0);
+    assert_eq!(float(v + 12 * cell + 4), 2.0);
+    assert_eq!(float(v + 12 * cell + 8), 3.0);
+    let b = find(&out, "SCALARS blockage int 1\n") + 23 + table.len();
+    assert_eq!(
+        i32::from_be_bytes(out[b + 12..b + 16].try_into().unwrap()),
+        0b111111
+    );
+    assert_eq!(out.len(), b + 4 * 24 + 1);
+}
+
+#[test]
+fn vtk_series_indexes_frames() {
+    let dir = std::env::temp_dir().join(format!("fluid_vtk_series_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let mut domain: Domain<1, 3, 3, 3> = Default::default();
+    let mut series = VtkSeries::new(&dir, "smoke");
+    for step in 0..3 {
+        domain.simulate();
+        let path = series.write(&domain, step as f32 * 0.1).unwrap();
+        assert!(path.exists());
+    }
+    assert_eq!(series.frames(), 3);
+    let index = std::fs::read_to_string(series.index_path()).unwrap();
+    assert!(index.contains("\"name\": \"smoke_00000.vtk\", \"time\": 0 }"));
+    assert!(index.contains("\"name\": \"smoke_00002.vtk\", \"time\": 0.2 }"));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn vtk_series_index_stays_valid_json() {
+    let dir = std::env::temp_dir().join(format!("fluid_vtk_escape_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let domain: Domain<1, 2, 2, 2> = Default::default();
+    let mut series = VtkSeries::new(&dir, "a \"b\" \\ c");
+    series.write(&domain, 0.5).unwrap();
+    let index = std::fs::read_to_string(series.index_path()).unwrap();
+    assert!(
+        index.contains(r#""name": "a \"b\" \\ c_00000.vtk""#),
+        "{}",
+        index
+    );
+
+    for time in [f32::NAN, f32::INFINITY] {
+        let error = series.write(&domain, time).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+    }
+    assert_eq!(series.frames(), 1);
+    assert_eq!(std::fs::read_to_string(series.index_path()).unwrap(), index);
+    std::fs::remove_dir_all(&dir).unwrap();
+}